@@ -0,0 +1,139 @@
+use chrono::Utc;
+use criterion::{Criterion, criterion_group, criterion_main};
+use ham_activity::bands::BandPlan;
+use ham_activity::spot_db::{ActivityBucket, ActivityQuery, BandActivity, CallInfo, Spot, SpotDB};
+use std::collections::{HashMap, HashSet};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Duration;
+use uom::si::f64::Frequency;
+use uom::si::frequency::kilohertz;
+
+const NUM_SPOTS: usize = 100_000;
+const SPOTTER_PREFIXES: &[&str] = &["DL", "F", "G", "HB9", "I", "OH", "K", "W", "JA", "VK"];
+const EU_PREFIXES: &[&str] = &["DL", "F", "G", "HB9", "I", "OH"];
+const BAND_EDGES_KHZ: &[f64] = &[1_810.0, 3_500.0, 7_000.0, 10_100.0, 14_000.0, 21_000.0];
+
+/// Roughly 30 minutes of a busy RBN feed: 300 skimmers, 5000 stations.
+fn busy_db() -> SpotDB {
+    let mut db = SpotDB::default();
+    db.add_region(
+        "EU".to_string(),
+        EU_PREFIXES.iter().map(ToString::to_string).collect(),
+    );
+    let now = Utc::now();
+    // simple LCG, good enough to spread the spots
+    let mut seed: u64 = 42;
+    let mut next = || {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        (seed >> 33) as usize
+    };
+    for _ in 0..NUM_SPOTS {
+        let spotter_nr = next() % 300;
+        let prefix = SPOTTER_PREFIXES[spotter_nr % SPOTTER_PREFIXES.len()];
+        let spotter = format!("{prefix}{spotter_nr}SK");
+        let spotted = format!("CALL{}", next() % 5_000);
+        let band = BAND_EDGES_KHZ[next() % BAND_EDGES_KHZ.len()];
+        let freq = Frequency::new::<kilohertz>(band + (next() % 60) as f64 + 0.1);
+        let age = Duration::from_secs((next() % 1_800) as u64);
+        db.add_spot(&spotter, &spotted, freq, "CW", 10, 25, "CQ", now - age);
+    }
+    db
+}
+
+/// The activity as computed before the region aggregates: every request
+/// clones and rescans all spots of the region's skimmers.
+fn full_rescan(
+    region_spots: &[Arc<Spot>],
+    band_plan: &BandPlan,
+    windows: &[Duration],
+) -> (Vec<BandActivity>, Vec<String>, HashMap<String, CallInfo>) {
+    let mut band_activity = HashMap::new();
+    let mut spotters = Vec::new();
+    let mut call_info: HashMap<String, CallInfo> = HashMap::new();
+    for band in band_plan.bands() {
+        band_activity.insert(
+            band.name.clone(),
+            BandActivity {
+                band: band.name.to_string(),
+                buckets: windows
+                    .iter()
+                    .map(|w| ActivityBucket {
+                        window_mins: w.as_secs() / 60,
+                        calls: Vec::new(),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        );
+    }
+    let now = Utc::now();
+    for spot in region_spots.iter().cloned() {
+        spotters.push(spot.spotter.clone());
+        if let Some(band) = &spot.band {
+            let ba = band_activity
+                .get_mut(band)
+                .expect("initialized hashmap is missing entry!");
+            // only list the newest spot, ignore longer windows
+            if let Some(i) = windows.iter().position(|w| now - *w < spot.timestamp) {
+                ba.buckets[i].calls.push(spot.spotted.clone());
+                let info = call_info.entry(spot.spotted.clone()).or_default();
+                info.frequencies.push(spot.freq.get::<kilohertz>());
+                info.frequencies.sort_by(f64::total_cmp);
+                info.frequencies.dedup();
+                info.wpm.push(spot.wpm);
+                info.wpm.sort_unstable();
+                info.wpm.dedup();
+                info.db.push(spot.snr_db);
+                info.db.sort_unstable();
+                info.db.dedup();
+            }
+        }
+    }
+    spotters.sort_unstable();
+    spotters.dedup();
+    let bands = band_plan
+        .bands()
+        .iter()
+        .map(|b| {
+            let mut ba = band_activity[&b.name].clone();
+            for bucket in &mut ba.buckets {
+                bucket.calls.sort_unstable();
+                bucket.calls.dedup();
+            }
+            ba
+        })
+        .collect();
+    (bands, spotters, call_info)
+}
+
+fn region_activity(c: &mut Criterion) {
+    let db = busy_db();
+    let region = db.get_region("EU").expect("region missing");
+    let query = ActivityQuery {
+        windows: [1, 5, 15]
+            .iter()
+            .map(|m| Duration::from_secs(m * 60))
+            .collect(),
+        ..Default::default()
+    };
+    let busted = HashSet::new();
+    // the spot list each region kept before the aggregates
+    let region_spots: Vec<Arc<Spot>> = db.region_spots(region).into_iter().cloned().collect();
+    c.bench_function("region_activity_full_rescan", |b| {
+        b.iter(|| black_box(full_rescan(&region_spots, db.band_plan(), &query.windows)))
+    });
+    c.bench_function("region_activity", |b| {
+        b.iter(|| black_box(region.compute_band_activities(db.band_plan(), &query, &busted)))
+    });
+    c.bench_function("region_activity_cached", |b| {
+        b.iter(|| black_box(region.get_band_activities(db.band_plan(), &query, &busted)))
+    });
+}
+
+fn ingest(c: &mut Criterion) {
+    c.bench_function("ingest_100k_spots", |b| b.iter(|| black_box(busy_db())));
+}
+
+criterion_group!(benches, region_activity, ingest);
+criterion_main!(benches);
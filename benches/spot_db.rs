@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use criterion::{Criterion, criterion_group, criterion_main};
+use ham_activity::spot_db::{ActivityQuery, FrequencyFilter, SpotDB};
+use std::hint::black_box;
+use std::time::Duration;
+use uom::si::f64::Frequency;
+use uom::si::frequency::kilohertz;
+
+const SPOTS_PER_MINUTE: usize = 300;
+const CLEANUP_PERIOD_MINS: usize = 15;
+const MAX_SPOT_AGE: Duration = Duration::from_secs(30 * 60);
+const SPOTTER_PREFIXES: &[&str] = &["DL", "F", "G", "HB9", "I", "OH", "K", "W", "JA", "VK"];
+const BAND_EDGES_KHZ: &[f64] = &[1_810.0, 3_500.0, 7_000.0, 10_100.0, 14_000.0, 21_000.0];
+
+/// Replays `minutes` of RBN traffic with periodic cleanup, a region and a
+/// frequency lookup every cleanup period.
+fn replay(minutes: usize) -> SpotDB {
+    let mut db = SpotDB::default();
+    db.add_region(
+        "EU".to_string(),
+        ["DL", "F", "G", "HB9", "I", "OH"]
+            .map(String::from)
+            .to_vec(),
+    );
+    db.add_region("NA".to_string(), ["K", "W"].map(String::from).to_vec());
+    db.add_region("OC".to_string(), ["VK"].map(String::from).to_vec());
+    let start: DateTime<Utc> = Utc::now() - Duration::from_secs(minutes as u64 * 60);
+    let query = ActivityQuery {
+        windows: [1, 5, 15].map(|m| Duration::from_secs(m * 60)).to_vec(),
+        ..Default::default()
+    };
+    // simple LCG, good enough to spread the spots
+    let mut seed: u64 = 42;
+    let mut next = || {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+        (seed >> 33) as usize
+    };
+    for minute in 0..minutes {
+        let now = start + Duration::from_secs(minute as u64 * 60);
+        for _ in 0..SPOTS_PER_MINUTE {
+            let spotter_nr = next() % 300;
+            let prefix = SPOTTER_PREFIXES[spotter_nr % SPOTTER_PREFIXES.len()];
+            let spotter = format!("{prefix}{spotter_nr}SK");
+            let spotted = format!("CALL{}", next() % 5_000);
+            let band = BAND_EDGES_KHZ[next() % BAND_EDGES_KHZ.len()];
+            let freq = Frequency::new::<kilohertz>(band + (next() % 60) as f64 + 0.1);
+            db.add_spot(&spotter, &spotted, freq, "CW", 10, 25, "CQ", now);
+        }
+        if minute % CLEANUP_PERIOD_MINS == 0 {
+            db.remove_spots_before(now - MAX_SPOT_AGE);
+            let region = db.get_region("EU").expect("region missing");
+            let busted = db.busted_calls();
+            black_box(region.compute_band_activities(db.band_plan(), &query, &busted));
+            let f = Frequency::new::<kilohertz>(14_030.1);
+            black_box(db.get_frequency_users(f, f, &FrequencyFilter::default()));
+        }
+    }
+    db
+}
+
+fn replay_hours(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spot_db");
+    group.sample_size(10);
+    group.bench_function("replay_2h", |b| b.iter(|| black_box(replay(2 * 60))));
+    group.bench_function("replay_24h", |b| b.iter(|| black_box(replay(24 * 60))));
+    group.finish();
+}
+
+criterion_group!(benches, replay_hours);
+criterion_main!(benches);
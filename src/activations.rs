@@ -0,0 +1,223 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::{fs, path::Path};
+
+/// An award program whose activators go out to references.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Program {
+    /// Parks on the Air
+    Pota,
+    /// Summits on the Air
+    Sota,
+    /// Islands on the Air
+    Iota,
+    /// World Wide Flora and Fauna
+    Wwff,
+}
+
+impl Program {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_uppercase().as_str() {
+            "POTA" => Some(Self::Pota),
+            "SOTA" => Some(Self::Sota),
+            "IOTA" => Some(Self::Iota),
+            "WWFF" => Some(Self::Wwff),
+            _ => None,
+        }
+    }
+}
+
+/// A park, summit, island or area a station is activating.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProgramReference {
+    pub program: Program,
+    /// e.g. "K-0001", "W7W/LC-001", "EU-005"
+    pub reference: String,
+    /// from the reference list, `None` if not listed
+    pub name: Option<String>,
+}
+
+/// A scheduled activation, as read from a schedule file.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Activation {
+    pub call: String,
+    pub program: Program,
+    pub reference: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Reference names and scheduled activations of the award programs.
+#[derive(Debug, Default)]
+pub struct Activations {
+    names: HashMap<(Program, String), String>,
+    // call -> activations
+    schedule: HashMap<String, Vec<Activation>>,
+}
+
+impl Activations {
+    pub fn load_references<P: AsRef<Path> + std::fmt::Display>(&mut self, path: P) -> Result<()> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        self.add_references(&text)
+            .map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// Reads one reference per line as "program,reference,name", e.g.
+    /// "POTA,K-0001,Acadia National Park". A header line starting with
+    /// "program" is skipped, `#` starts a comment.
+    pub fn add_references(&mut self, text: &str) -> Result<()> {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() || line.to_lowercase().starts_with("program,") {
+                continue;
+            }
+            let mut fields = line.splitn(3, ',').map(str::trim);
+            let (Some(program), Some(reference), Some(name)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                bail!("invalid reference line: {line}");
+            };
+            let Some(program) = Program::parse(program) else {
+                bail!("unknown program in: {line}");
+            };
+            let name = name.trim_matches('"').to_string();
+            self.names.insert((program, reference.to_uppercase()), name);
+        }
+        Ok(())
+    }
+
+    pub fn load_schedule<P: AsRef<Path> + std::fmt::Display>(&mut self, path: P) -> Result<()> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        self.add_schedule(&text)
+            .map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// Reads a JSON array of activations like
+    /// `{"call": "K1ABC", "program": "POTA", "reference": "K-0001",
+    /// "start": "2024-06-01T14:00:00Z", "end": "2024-06-01T18:00:00Z"}`.
+    pub fn add_schedule(&mut self, text: &str) -> Result<()> {
+        let activations: Vec<Activation> = serde_json::from_str(text)?;
+        for mut activation in activations {
+            if activation.start > activation.end {
+                bail!(
+                    "activation of {} at {} ends before it starts",
+                    activation.call,
+                    activation.reference
+                );
+            }
+            activation.call = activation.call.to_uppercase();
+            activation.reference = activation.reference.to_uppercase();
+            self.schedule
+                .entry(activation.call.clone())
+                .or_default()
+                .push(activation);
+        }
+        Ok(())
+    }
+
+    /// References `call` is scheduled to activate at `time`, sorted. A
+    /// portable call like "K1ABC/P" also matches the schedule of K1ABC.
+    pub fn active(&self, call: &str, time: DateTime<Utc>) -> Vec<ProgramReference> {
+        let activations = self.schedule.get(call).or_else(|| {
+            let base = call.split('/').max_by_key(|p| p.len())?;
+            self.schedule.get(base)
+        });
+        let mut references: Vec<ProgramReference> = activations
+            .into_iter()
+            .flatten()
+            .filter(|a| a.start <= time && time <= a.end)
+            .map(|a| ProgramReference {
+                program: a.program,
+                reference: a.reference.clone(),
+                name: self.names.get(&(a.program, a.reference.clone())).cloned(),
+            })
+            .collect();
+        references.sort_unstable();
+        references.dedup();
+        references
+    }
+}
+
+/// Parses "pota,SOTA" into sorted, deduplicated programs. `None` if a
+/// program is unknown.
+pub fn parse_programs(programs: &str) -> Option<Vec<Program>> {
+    let mut programs = programs
+        .split(',')
+        .filter(|p| !p.trim().is_empty())
+        .map(Program::parse)
+        .collect::<Option<Vec<Program>>>()?;
+    programs.sort_unstable();
+    programs.dedup();
+    Some(programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    const REFERENCES: &str = "\
+program,reference,name
+POTA,K-0001,Acadia National Park
+sota,W7W/LC-001,\"Mount Rainier, WA\" # quoted
+";
+
+    const SCHEDULE: &str = r#"[
+        {"call": "k1abc", "program": "POTA", "reference": "K-0001",
+         "start": "2024-06-01T14:00:00Z", "end": "2024-06-01T18:00:00Z"},
+        {"call": "K1ABC", "program": "WWFF", "reference": "KFF-0001",
+         "start": "2024-06-01T14:00:00Z", "end": "2024-06-01T18:00:00Z"},
+        {"call": "W7ABC", "program": "SOTA", "reference": "W7W/LC-001",
+         "start": "2024-06-02T14:00:00Z", "end": "2024-06-02T16:00:00Z"}
+    ]"#;
+
+    #[rstest]
+    fn active_references() {
+        let mut activations = Activations::default();
+        activations.add_references(REFERENCES).unwrap();
+        activations.add_schedule(SCHEDULE).unwrap();
+        let during = Utc.with_ymd_and_hms(2024, 6, 1, 15, 0, 0).unwrap();
+        let references = activations.active("K1ABC/P", during);
+        assert_eq!(
+            references,
+            vec![
+                ProgramReference {
+                    program: Program::Pota,
+                    reference: "K-0001".to_string(),
+                    name: Some("Acadia National Park".to_string()),
+                },
+                ProgramReference {
+                    program: Program::Wwff,
+                    reference: "KFF-0001".to_string(),
+                    name: None,
+                },
+            ]
+        );
+        let after = Utc.with_ymd_and_hms(2024, 6, 1, 18, 1, 0).unwrap();
+        assert!(activations.active("K1ABC", after).is_empty());
+        let summit = Utc.with_ymd_and_hms(2024, 6, 2, 15, 0, 0).unwrap();
+        assert_eq!(
+            activations.active("W7ABC", summit)[0].name.as_deref(),
+            Some("Mount Rainier, WA")
+        );
+    }
+
+    #[rstest]
+    #[case("XOTA,K-0001,Nowhere")]
+    #[case("POTA,K-0001")]
+    fn invalid_references(#[case] text: &str) {
+        assert!(Activations::default().add_references(text).is_err());
+    }
+
+    #[rstest]
+    #[case("pota,SOTA,pota", Some(vec![Program::Pota, Program::Sota]))]
+    #[case("", Some(vec![]))]
+    #[case("pota,xota", None)]
+    fn parse_program_list(#[case] programs: &str, #[case] expected: Option<Vec<Program>>) {
+        assert_eq!(parse_programs(programs), expected);
+    }
+}
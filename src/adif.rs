@@ -0,0 +1,117 @@
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashMap;
+use std::{fs, path::Path};
+
+/// The fields of a logged QSO needed for award tracking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qso {
+    pub call: String,
+    /// lower case like in the band plan, e.g. "20m"
+    pub band: String,
+    /// the submode if there is one, e.g. FT4 instead of MFSK, upper case
+    pub mode: String,
+    /// confirmed by QSL card or LoTW
+    pub confirmed: bool,
+}
+
+pub fn load<P: AsRef<Path> + std::fmt::Display>(path: P) -> Result<Vec<Qso>> {
+    let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+    parse(&text).map_err(|e| anyhow!("failed to read {path}: {e}"))
+}
+
+impl Qso {
+    fn from_fields(fields: &HashMap<String, &str>) -> Option<Self> {
+        let field = |name: &str| fields.get(name).map(|v| v.trim()).filter(|v| !v.is_empty());
+        let confirmed = ["QSL_RCVD", "LOTW_QSL_RCVD"]
+            .iter()
+            .any(|f| field(f).is_some_and(|v| v.eq_ignore_ascii_case("Y")));
+        Some(Self {
+            call: field("CALL")?.to_uppercase(),
+            band: field("BAND")?.to_lowercase(),
+            mode: field("SUBMODE").or(field("MODE"))?.to_uppercase(),
+            confirmed,
+        })
+    }
+}
+
+/// Parses the QSOs of an ADIF (.adi) log. Records without call, band or
+/// mode are skipped.
+pub fn parse(text: &str) -> Result<Vec<Qso>> {
+    let mut qsos = Vec::new();
+    let mut fields: HashMap<String, &str> = HashMap::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            bail!("unterminated tag at {}", &rest[start..]);
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+        let mut parts = tag.split(':');
+        let name = parts.next().unwrap_or_default().trim().to_uppercase();
+        match name.as_str() {
+            "EOH" => fields.clear(),
+            "EOR" => {
+                if let Some(qso) = Qso::from_fields(&fields) {
+                    qsos.push(qso);
+                }
+                fields.clear();
+            }
+            _ => {
+                let len: usize = parts
+                    .next()
+                    .ok_or_else(|| anyhow!("no length in <{tag}>"))?
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow!("invalid length in <{tag}>: {e}"))?;
+                let value = rest
+                    .get(..len)
+                    .ok_or_else(|| anyhow!("value of <{tag}> too short"))?;
+                fields.insert(name, value);
+                rest = &rest[len..];
+            }
+        }
+    }
+    Ok(qsos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn parse_log() {
+        let text = "Exported log <ADIF_VER:5>3.1.4 <EOH>
+<CALL:6>JA1ABC <BAND:3>20M <MODE:2>CW <QSL_RCVD:1>Y <EOR>
+<call:5>k1abc<band:3>40m<mode:4>MFSK<submode:3>FT4<lotw_qsl_rcvd:1>N<eor>
+<CALL:5>W1XYZ <MODE:3>SSB <EOR>
+";
+        let qsos = parse(text).unwrap();
+        assert_eq!(
+            qsos,
+            vec![
+                Qso {
+                    call: "JA1ABC".to_string(),
+                    band: "20m".to_string(),
+                    mode: "CW".to_string(),
+                    confirmed: true,
+                },
+                Qso {
+                    call: "K1ABC".to_string(),
+                    band: "40m".to_string(),
+                    mode: "FT4".to_string(),
+                    confirmed: false,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("<CALL:6>JA1")]
+    #[case("<CALL>JA1ABC<EOR>")]
+    #[case("<CALL:x>JA1ABC<EOR>")]
+    #[case("<CALL:6")]
+    fn invalid_log(#[case] text: &str) {
+        assert!(parse(text).is_err());
+    }
+}
@@ -1,41 +1,273 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::{fs, path::Path};
 use uom::si::f64::Frequency;
+use uom::si::frequency::kilohertz;
 
+/// Band plan shipped with the binary, used if no file is configured.
+const DEFAULT_BAND_PLAN: &str = include_str!("../data/bandplan.yaml");
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentKind {
+    Cw,
+    Digi,
+    Phone,
+    Beacon,
+}
+
+impl SegmentKind {
+    /// The segment spots of `mode` belong in, `None` for unknown modes.
+    pub fn for_mode(mode: &str) -> Option<Self> {
+        match mode {
+            "CW" => Some(Self::Cw),
+            "FT8" | "FT4" | "RTTY" | "PSK31" | "PSK63" => Some(Self::Digi),
+            "SSB" | "USB" | "LSB" | "AM" | "FM" => Some(Self::Phone),
+            _ => None,
+        }
+    }
+}
+
+/// A part of a band reserved for one kind of operation.
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub lower: Frequency,
+    pub upper: Frequency,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Band {
-    pub name: &'static str,
+    pub name: String,
     pub lower: Frequency,
     pub upper: Frequency,
+    pub segments: Vec<Segment>,
 }
 
 impl Band {
-    /// Const constructor that receives the limits in kHz.
-    pub const fn new_from_khz(lower_khz: u64, upper_khz: u64, name: &'static str) -> Self {
-        // new does not (yet?) support const fn, hence create Frequency manually
-        let lower = Frequency {
-            dimension: uom::lib::marker::PhantomData,
-            units: uom::lib::marker::PhantomData,
-            value: (lower_khz * 1_000) as f64,
-        };
+    pub fn contains(&self, freq: Frequency) -> bool {
+        self.lower <= freq && freq <= self.upper
+    }
+
+    /// Returns the segment containing `freq`, the first one wins on a shared
+    /// edge.
+    pub fn find_segment(&self, freq: Frequency) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|s| s.lower <= freq && freq <= s.upper)
+    }
 
-        let upper = Frequency {
-            dimension: uom::lib::marker::PhantomData,
-            units: uom::lib::marker::PhantomData,
-            value: (upper_khz * 1_000) as f64,
+    /// Whether a spot of `mode` on `freq` is in no segment, or in a segment
+    /// of another kind than the mode's. Never on bands without segments or
+    /// for unknown modes.
+    pub fn out_of_segment(&self, freq: Frequency, mode: &str) -> bool {
+        if self.segments.is_empty() {
+            return false;
+        }
+        let Some(expected) = SegmentKind::for_mode(mode) else {
+            return false;
         };
+        self.find_segment(freq).is_none_or(|s| s.kind != expected)
+    }
+}
+
+/// The bands of one IARU region, ordered by frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandPlan {
+    pub iaru_region: u8,
+    bands: Vec<Band>,
+}
+
+// file format, all frequencies in kHz
+#[derive(Debug, Deserialize)]
+struct BandPlanFile {
+    regions: HashMap<u8, Vec<BandEntry>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BandEntry {
+    name: String,
+    lower: f64,
+    upper: f64,
+    #[serde(default)]
+    segments: Vec<SegmentEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SegmentEntry {
+    kind: SegmentKind,
+    lower: f64,
+    upper: f64,
+}
 
-        Self { name, lower, upper }
+/// Checks that every band and segment ends above its start, that segments
+/// lie in their band and that neither bands nor the segments of a band
+/// overlap. `bands` must be sorted by their lower edge.
+fn validate(bands: &[Band]) -> Result<()> {
+    for band in bands {
+        if band.lower >= band.upper {
+            bail!("band {} ends below its start", band.name);
+        }
+        let mut segments: Vec<&Segment> = band.segments.iter().collect();
+        segments.sort_by(|a, b| a.lower.value.total_cmp(&b.lower.value));
+        for segment in &segments {
+            if segment.lower >= segment.upper {
+                bail!("a segment of band {} ends below its start", band.name);
+            }
+            if segment.lower < band.lower || segment.upper > band.upper {
+                bail!("a segment of band {} lies outside of it", band.name);
+            }
+        }
+        if segments.windows(2).any(|w| w[0].upper > w[1].lower) {
+            bail!("segments of band {} overlap", band.name);
+        }
+    }
+    if let Some(w) = bands.windows(2).find(|w| w[0].upper > w[1].lower) {
+        bail!("bands {} and {} overlap", w[0].name, w[1].name);
     }
+    Ok(())
 }
 
-pub const HF_BANDS: &[Band] = &[
-    Band::new_from_khz(1_800, 2_000, "160m"),
-    Band::new_from_khz(3_500, 4_000, "80m"),
-    Band::new_from_khz(7_000, 7_200, "40m"),
-    Band::new_from_khz(10_100, 10_150, "30m"),
-    Band::new_from_khz(14_000, 14_350, "20m"),
-    Band::new_from_khz(18_068, 18_168, "17m"),
-    Band::new_from_khz(21_000, 21_450, "15m"),
-    Band::new_from_khz(24_890, 24_990, "12m"),
-    Band::new_from_khz(28_000, 29_700, "10m"),
-    Band::new_from_khz(50_000, 52_000, "6m"),
-];
+impl BandPlan {
+    /// Loads the band plan of `iaru_region` (1, 2 or 3) from a yaml file.
+    pub fn load<P: AsRef<Path> + std::fmt::Display>(path: P, iaru_region: u8) -> Result<Self> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        Self::from_yaml(&text, iaru_region).map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// The band plan of `iaru_region` from the data shipped with the binary.
+    pub fn builtin(iaru_region: u8) -> Result<Self> {
+        Self::from_yaml(DEFAULT_BAND_PLAN, iaru_region)
+    }
+
+    pub fn from_yaml(text: &str, iaru_region: u8) -> Result<Self> {
+        let mut file: BandPlanFile = serde_yaml::from_str(text)?;
+        let entries = file
+            .regions
+            .remove(&iaru_region)
+            .ok_or_else(|| anyhow!("no band plan for IARU region {iaru_region}"))?;
+        let khz = Frequency::new::<kilohertz>;
+        let mut bands: Vec<Band> = entries
+            .into_iter()
+            .map(|b| Band {
+                name: b.name,
+                lower: khz(b.lower),
+                upper: khz(b.upper),
+                segments: b
+                    .segments
+                    .into_iter()
+                    .map(|s| Segment {
+                        kind: s.kind,
+                        lower: khz(s.lower),
+                        upper: khz(s.upper),
+                    })
+                    .collect(),
+            })
+            .collect();
+        bands.sort_by(|a, b| a.lower.value.total_cmp(&b.lower.value));
+        validate(&bands)?;
+        Ok(Self { iaru_region, bands })
+    }
+
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    /// Returns the band containing `freq`, if any.
+    pub fn find_band(&self, freq: Frequency) -> Option<&Band> {
+        self.bands.iter().find(|b| b.contains(freq))
+    }
+
+    /// Returns the band with the given name, e.g. "20m".
+    pub fn find_band_by_name(&self, name: &str) -> Option<&Band> {
+        self.bands.iter().find(|b| b.name == name)
+    }
+}
+
+impl Default for BandPlan {
+    /// IARU region 1 from the built-in band plan.
+    fn default() -> Self {
+        Self::builtin(1).expect("built-in band plan is invalid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1, 7_150.0, Some("40m"))]
+    #[case(1, 7_250.0, None)]
+    #[case(2, 7_250.0, Some("40m"))]
+    #[case(1, 5_355.0, Some("60m"))]
+    #[case(1, 70_100.0, Some("4m"))]
+    #[case(2, 70_100.0, None)]
+    #[case(3, 432_050.0, Some("70cm"))]
+    fn find_band(#[case] region: u8, #[case] khz: f64, #[case] expected: Option<&str>) {
+        let plan = BandPlan::builtin(region).unwrap();
+        let band = plan.find_band(Frequency::new::<kilohertz>(khz));
+        assert_eq!(band.map(|b| b.name.as_str()), expected);
+    }
+
+    #[rstest]
+    #[case(14_025.0, Some(SegmentKind::Cw))]
+    #[case(14_074.0, Some(SegmentKind::Digi))]
+    #[case(14_100.0, Some(SegmentKind::Beacon))]
+    #[case(14_200.0, Some(SegmentKind::Phone))]
+    fn find_segment(#[case] khz: f64, #[case] expected: Option<SegmentKind>) {
+        let plan = BandPlan::default();
+        let freq = Frequency::new::<kilohertz>(khz);
+        let segment = plan.find_band(freq).and_then(|b| b.find_segment(freq));
+        assert_eq!(segment.map(|s| s.kind), expected);
+    }
+
+    #[rstest]
+    #[case(14025.0, "CW", false)]
+    #[case(14025.0, "FT8", true)]
+    #[case(14074.0, "FT8", false)]
+    #[case(14100.0, "CW", true)]
+    #[case(14200.0, "SSB", false)]
+    #[case(14200.0, "RTTY", true)]
+    #[case(14025.0, "OLIVIA", false)]
+    fn out_of_segment(#[case] khz: f64, #[case] mode: &str, #[case] expected: bool) {
+        let plan = BandPlan::default();
+        let freq = Frequency::new::<kilohertz>(khz);
+        let band = plan.find_band(freq).unwrap();
+        assert_eq!(band.out_of_segment(freq, mode), expected);
+    }
+
+    #[rstest]
+    fn unknown_region() {
+        assert!(BandPlan::builtin(4).is_err());
+    }
+
+    const BAND: &str = "regions:\n  1:\n    - {name: 20m, lower: 14000, upper: 14350, segments: [";
+
+    #[rstest]
+    #[case(
+        "{kind: cw, lower: 14000, upper: 14070}, {kind: digi, lower: 14070, upper: 14099}]}",
+        true
+    )]
+    #[case("{kind: cw, lower: 14070, upper: 14000}]}", false)]
+    #[case(
+        "{kind: cw, lower: 14000, upper: 14080}, {kind: digi, lower: 14070, upper: 14099}]}",
+        false
+    )]
+    #[case("{kind: cw, lower: 13990, upper: 14070}]}", false)]
+    #[case("]}\n    - {name: 17m, lower: 14300, upper: 14400}", false)]
+    #[case("]}\n    - {name: 17m, lower: 18068, upper: 18000}", false)]
+    fn validate_band_plan(#[case] segments: &str, #[case] valid: bool) {
+        let yaml = format!("{BAND}{segments}");
+        assert_eq!(BandPlan::from_yaml(&yaml, 1).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(3)]
+    fn builtin_band_plans_are_valid(#[case] region: u8) {
+        assert!(BandPlan::builtin(region).is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+
+/// A beacon of the NCDXF/IARU International Beacon Project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NcdxfBeacon {
+    pub callsign: &'static str,
+    pub location: &'static str,
+}
+
+/// The 18 beacons in the order of their transmit schedule.
+pub const NCDXF_BEACONS: [NcdxfBeacon; 18] = [
+    NcdxfBeacon {
+        callsign: "4U1UN",
+        location: "United Nations, New York",
+    },
+    NcdxfBeacon {
+        callsign: "VE8AT",
+        location: "Eureka, Canada",
+    },
+    NcdxfBeacon {
+        callsign: "W6WX",
+        location: "Mt. Umunhum, California",
+    },
+    NcdxfBeacon {
+        callsign: "KH6RS",
+        location: "Maui, Hawaii",
+    },
+    NcdxfBeacon {
+        callsign: "ZL6B",
+        location: "Masterton, New Zealand",
+    },
+    NcdxfBeacon {
+        callsign: "VK6RBP",
+        location: "Rolystone, Australia",
+    },
+    NcdxfBeacon {
+        callsign: "JA2IGY",
+        location: "Mt. Asama, Japan",
+    },
+    NcdxfBeacon {
+        callsign: "RR9O",
+        location: "Novosibirsk, Russia",
+    },
+    NcdxfBeacon {
+        callsign: "VR2B",
+        location: "Hong Kong",
+    },
+    NcdxfBeacon {
+        callsign: "4S7B",
+        location: "Colombo, Sri Lanka",
+    },
+    NcdxfBeacon {
+        callsign: "ZS6DN",
+        location: "Pretoria, South Africa",
+    },
+    NcdxfBeacon {
+        callsign: "5Z4B",
+        location: "Kariobangi, Kenya",
+    },
+    NcdxfBeacon {
+        callsign: "4X6TU",
+        location: "Tel Aviv, Israel",
+    },
+    NcdxfBeacon {
+        callsign: "OH2B",
+        location: "Lohja, Finland",
+    },
+    NcdxfBeacon {
+        callsign: "CS3B",
+        location: "Madeira",
+    },
+    NcdxfBeacon {
+        callsign: "LU4AA",
+        location: "Buenos Aires, Argentina",
+    },
+    NcdxfBeacon {
+        callsign: "OA4B",
+        location: "Lima, Peru",
+    },
+    NcdxfBeacon {
+        callsign: "YV5B",
+        location: "Caracas, Venezuela",
+    },
+];
+
+/// Beacon frequencies in kHz, each beacon steps through them in this order.
+pub const NCDXF_FREQUENCIES_KHZ: [f64; 5] = [14_100.0, 18_110.0, 21_150.0, 24_930.0, 28_200.0];
+
+/// Every beacon transmits this long on a frequency, the whole cycle takes
+/// 18 slots, i.e. 3 minutes, and starts at the full hour.
+const SLOT_SECS: i64 = 10;
+
+/// The beacon on the frequency with index `frequency` (into
+/// `NCDXF_FREQUENCIES_KHZ`) at `time`.
+pub fn ncdxf_transmitting(frequency: usize, time: DateTime<Utc>) -> &'static NcdxfBeacon {
+    let n = NCDXF_BEACONS.len();
+    let slot = (time.timestamp().rem_euclid(SLOT_SECS * n as i64) / SLOT_SECS) as usize;
+    &NCDXF_BEACONS[(slot + n - frequency % n) % n]
+}
+
+pub fn is_ncdxf_beacon(callsign: &str) -> bool {
+    NCDXF_BEACONS.iter().any(|b| b.callsign == callsign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0, "4U1UN")]
+    #[case(0, 10, "VE8AT")]
+    #[case(1, 10, "4U1UN")]
+    #[case(4, 40, "4U1UN")]
+    #[case(1, 0, "YV5B")]
+    #[case(0, 175, "YV5B")]
+    #[case(0, 180, "4U1UN")]
+    fn schedule(#[case] frequency: usize, #[case] secs: u32, #[case] expected: &str) {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+            + chrono::Duration::seconds(i64::from(secs));
+        assert_eq!(ncdxf_transmitting(frequency, time).callsign, expected);
+    }
+}
@@ -0,0 +1,365 @@
+use anyhow::{Result, anyhow, bail};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{fs, path::Path};
+
+/// Cached lookups beyond this purge the expired entries, then the oldest.
+const MAX_CACHED: usize = 10_000;
+/// Provider lookups of one `lookup_all`, the other uncached calls are left
+/// to later requests.
+const MAX_LOOKUPS: usize = 50;
+/// Provider lookups of `lookup_all` running at the same time.
+const LOOKUP_THREADS: usize = 8;
+/// HTTP lookups taking longer than this fail.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a callbook knows about a station.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct CallbookEntry {
+    pub name: Option<String>,
+    /// Maidenhead locator
+    pub grid: Option<String>,
+    pub country: Option<String>,
+}
+
+/// Where station details come from.
+pub trait CallbookProvider: Send + Sync {
+    /// Entry of `call`, `None` if the callbook does not know it. Blocks
+    /// while looking up.
+    fn lookup(&self, call: &str) -> Result<Option<CallbookEntry>>;
+}
+
+/// Stations from a local CSV file, e.g. a dump of licensing data.
+pub struct FileProvider {
+    entries: HashMap<String, CallbookEntry>,
+}
+
+/// Splits a CSV line, fields may be quoted to contain commas.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().expect("never empty").push(c),
+        }
+    }
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+impl FileProvider {
+    pub fn load<P: AsRef<Path> + std::fmt::Display>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        Self::parse(&text).map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// Reads one station per line as "call,name,grid,country", empty fields
+    /// are unknown. A header line starting with "call" is skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries = HashMap::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.to_lowercase().starts_with("call,") {
+                continue;
+            }
+            let fields = split_csv(line);
+            let [call, name, grid, country] = &fields[..] else {
+                bail!("invalid callbook line: {line}");
+            };
+            let field = |f: &String| Some(f.clone()).filter(|f| !f.is_empty());
+            entries.insert(
+                call.to_uppercase(),
+                CallbookEntry {
+                    name: field(name),
+                    grid: field(grid),
+                    country: field(country),
+                },
+            );
+        }
+        Ok(Self { entries })
+    }
+}
+
+impl CallbookProvider for FileProvider {
+    fn lookup(&self, call: &str) -> Result<Option<CallbookEntry>> {
+        Ok(self.entries.get(call).cloned())
+    }
+}
+
+/// Looks stations up at an HTTP callbook API. `{call}` in the URL is
+/// replaced by the percent-encoded callsign, the response is a JSON object
+/// with name, grid and country, 404 if the call is unknown.
+pub struct HttpProvider {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpProvider {
+    pub fn new(url: String) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(HTTP_TIMEOUT))
+            .build()
+            .into();
+        Self { url, agent }
+    }
+}
+
+/// Percent-encodes all but the unreserved characters of a URL.
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+impl CallbookProvider for HttpProvider {
+    fn lookup(&self, call: &str) -> Result<Option<CallbookEntry>> {
+        let url = self.url.replace("{call}", &percent_encode(call));
+        match self.agent.get(&url).call() {
+            Ok(mut response) => {
+                let text = response
+                    .body_mut()
+                    .read_to_string()
+                    .map_err(|e| anyhow!("failed to read {url}: {e}"))?;
+                let entry = serde_json::from_str(&text)
+                    .map_err(|e| anyhow!("failed to read {url}: {e}"))?;
+                Ok(Some(entry))
+            }
+            Err(ureq::Error::StatusCode(404)) => Ok(None),
+            Err(e) => Err(anyhow!("failed to fetch {url}: {e}")),
+        }
+    }
+}
+
+/// A provider for `source`, an HTTP API if it is an http(s) URL, else the
+/// local CSV file.
+pub fn provider(source: &str) -> Result<Arc<dyn CallbookProvider>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        Ok(Arc::new(HttpProvider::new(source.to_string())))
+    } else {
+        Ok(Arc::new(FileProvider::load(source)?))
+    }
+}
+
+/// Keeps lookups of a provider, unknown calls included, for a while.
+pub struct CallbookCache {
+    provider: Arc<dyn CallbookProvider>,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Option<CallbookEntry>)>>,
+}
+
+impl CallbookCache {
+    pub fn new(provider: Arc<dyn CallbookProvider>, ttl: Duration) -> Self {
+        Self {
+            provider,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached lookup of `call`, `None` if not cached within the TTL.
+    fn cached(&self, call: &str) -> Option<Option<CallbookEntry>> {
+        let entries = self.entries.lock().expect("Mutex poisoned");
+        let (looked_up, entry) = entries.get(call)?;
+        (looked_up.elapsed() < self.ttl).then(|| entry.clone())
+    }
+
+    /// Entry of `call`, looked up by the provider unless cached within the
+    /// TTL. Failed lookups are logged and not cached.
+    pub fn lookup(&self, call: &str) -> Option<CallbookEntry> {
+        if let Some(entry) = self.cached(call) {
+            return entry;
+        }
+        // don't hold the lock while the provider blocks
+        let entry = match self.provider.lookup(call) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("callbook lookup of {call} failed: {e}");
+                return None;
+            }
+        };
+        let mut entries = self.entries.lock().expect("Mutex poisoned");
+        if entries.len() >= MAX_CACHED {
+            entries.retain(|_, (looked_up, _)| looked_up.elapsed() < self.ttl);
+        }
+        if entries.len() >= MAX_CACHED {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (looked_up, _))| *looked_up)
+                .map(|(call, _)| call.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(call.to_string(), (Instant::now(), entry.clone()));
+        entry
+    }
+
+    /// Entries of the known `calls`. Cached calls are answered right away,
+    /// at most `MAX_LOOKUPS` others are looked up by the provider on a few
+    /// threads, the rest are left out until a later call caches them.
+    pub fn lookup_all<'a>(
+        &self,
+        calls: impl IntoIterator<Item = &'a str>,
+    ) -> HashMap<String, CallbookEntry> {
+        let mut entries = HashMap::new();
+        let mut uncached = Vec::new();
+        for call in calls {
+            match self.cached(call) {
+                Some(entry) => entries.extend(entry.map(|e| (call.to_string(), e))),
+                None if uncached.len() < MAX_LOOKUPS => uncached.push(call),
+                None => {}
+            }
+        }
+        let chunk_size = uncached.len().div_ceil(LOOKUP_THREADS).max(1);
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = uncached
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .filter_map(|call| Some((call.to_string(), self.lookup(call)?)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for thread in threads {
+                entries.extend(thread.join().expect("callbook lookup panicked"));
+            }
+        });
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const CSV: &str = "\
+call,name,grid,country
+K1ABC,\"Smith, John\",FN42,United States
+JA1XYZ,,PM95,Japan
+";
+
+    #[rstest]
+    fn file_provider() {
+        let provider = FileProvider::parse(CSV).unwrap();
+        assert_eq!(
+            provider.lookup("K1ABC").unwrap(),
+            Some(CallbookEntry {
+                name: Some("Smith, John".to_string()),
+                grid: Some("FN42".to_string()),
+                country: Some("United States".to_string()),
+            })
+        );
+        assert_eq!(provider.lookup("JA1XYZ").unwrap().unwrap().name, None);
+        assert_eq!(provider.lookup("W1AW").unwrap(), None);
+        assert!(FileProvider::parse("K1ABC,John").is_err());
+    }
+
+    /// Serves `requests` lookups of a callbook that only knows K1ABC and
+    /// K1ABC/P.
+    fn mock_callbook(requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+                let known = ["/K1ABC ", "/K1ABC%2FP "];
+                let (status, body) = if known.iter().any(|k| request_line.contains(k)) {
+                    (
+                        "200 OK",
+                        r#"{"name": "John", "grid": "FN42", "country": null}"#,
+                    )
+                } else {
+                    ("404 Not Found", "")
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://127.0.0.1:{port}/lookup/{{call}}")
+    }
+
+    #[rstest]
+    fn http_provider() {
+        let provider = provider(&mock_callbook(3)).unwrap();
+        let entry = provider.lookup("K1ABC").unwrap().unwrap();
+        assert_eq!(entry.grid.as_deref(), Some("FN42"));
+        assert_eq!(entry.country, None);
+        assert_eq!(provider.lookup("W1AW").unwrap(), None);
+        assert!(provider.lookup("K1ABC/P").unwrap().is_some());
+    }
+
+    struct CountingProvider(AtomicUsize);
+
+    impl CallbookProvider for CountingProvider {
+        fn lookup(&self, call: &str) -> Result<Option<CallbookEntry>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            if call == "FAIL" {
+                bail!("callbook down");
+            }
+            Ok((call == "K1ABC").then(CallbookEntry::default))
+        }
+    }
+
+    #[rstest]
+    #[case(Duration::from_secs(60), 3)]
+    #[case(Duration::ZERO, 5)]
+    fn cache_ttl(#[case] ttl: Duration, #[case] expected_lookups: usize) {
+        let provider = Arc::new(CountingProvider(AtomicUsize::new(0)));
+        let cache = CallbookCache::new(provider.clone(), ttl);
+        assert!(cache.lookup("K1ABC").is_some());
+        assert!(cache.lookup("K1ABC").is_some());
+        // unknown calls are cached, failures are not
+        assert!(cache.lookup("W1AW").is_none());
+        assert!(cache.lookup("W1AW").is_none());
+        assert!(cache.lookup("FAIL").is_none());
+        let lookups = provider.0.load(Ordering::SeqCst);
+        assert_eq!(lookups, expected_lookups);
+    }
+
+    #[rstest]
+    fn cache_evicts_oldest() {
+        let provider = Arc::new(CountingProvider(AtomicUsize::new(0)));
+        let cache = CallbookCache::new(provider.clone(), Duration::from_secs(60));
+        let calls: Vec<String> = (0..=MAX_CACHED).map(|i| format!("K{i}ABC")).collect();
+        for call in &calls {
+            cache.lookup(call);
+        }
+        assert_eq!(cache.entries.lock().unwrap().len(), MAX_CACHED);
+        assert!(cache.cached(&calls[MAX_CACHED]).is_some());
+    }
+
+    #[rstest]
+    fn lookup_all_caps_lookups() {
+        let provider = Arc::new(CountingProvider(AtomicUsize::new(0)));
+        let cache = CallbookCache::new(provider.clone(), Duration::from_secs(60));
+        assert!(cache.lookup("K1ABC").is_some());
+        let calls: Vec<String> = (0..2 * MAX_LOOKUPS).map(|i| format!("W{i}AW")).collect();
+        let entries =
+            cache.lookup_all(std::iter::once("K1ABC").chain(calls.iter().map(String::as_str)));
+        assert_eq!(entries.keys().collect::<Vec<_>>(), vec!["K1ABC"]);
+        // K1ABC was cached
+        assert_eq!(provider.0.load(Ordering::SeqCst), 1 + MAX_LOOKUPS);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::spot_db::ContestProfile;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::{fs, path::Path};
 
 #[derive(Debug, Deserialize)]
@@ -6,6 +8,23 @@ pub struct Config {
     pub rbn: RBNConfig,
     pub db: DBConfig,
     pub region_file: String,
+    #[serde(default)]
+    pub band_plan: BandPlanConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub beacons: BeaconConfig,
+    #[serde(default)]
+    pub geo: GeoConfig,
+    #[serde(default)]
+    pub space_weather: SpaceWeatherConfig,
+    #[serde(default)]
+    pub activations: ActivationConfig,
+    #[serde(default)]
+    pub callbook: CallbookConfig,
+    /// operators by name, see /region?user=
+    #[serde(default)]
+    pub users: HashMap<String, UserConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -23,10 +42,220 @@ pub struct RBNConfig {
 pub struct DBConfig {
     pub cleanup_period_secs: u64,
     pub max_spot_age_secs: u64,
+    #[serde(default = "default_calibration_period_secs")]
+    pub calibration_period_secs: u64,
+    /// oldest spots are evicted beyond this, out-of-band and beacon spots
+    /// included, unbounded if not set
+    #[serde(default)]
+    pub max_spots: Option<usize>,
+}
+
+fn default_calibration_period_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BandPlanConfig {
+    /// band plan file, the built-in one is used if not set
+    #[serde(default)]
+    pub file: Option<String>,
+    /// IARU region (1, 2 or 3) whose band edges and segments are used
+    #[serde(default = "default_iaru_region")]
+    pub iaru_region: u8,
+}
+
+fn default_iaru_region() -> u8 {
+    1
+}
+
+impl Default for BandPlanConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            iaru_region: default_iaru_region(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ApiConfig {
+    /// activity time windows of the region view, in minutes
+    #[serde(default = "default_activity_windows_mins")]
+    pub activity_windows_mins: Vec<u64>,
+    /// window of the propagation view, compared to the window before it
+    #[serde(default = "default_propagation_window_mins")]
+    pub propagation_window_mins: u64,
+    /// contest profile of the contest view (cqww or wpx), none if not set
+    #[serde(default)]
+    pub contest_profile: Option<ContestProfile>,
+    /// calls per hour of the contest view are counted over this window
+    #[serde(default = "default_contest_rate_window_mins")]
+    pub contest_rate_window_mins: u64,
+}
+
+fn default_activity_windows_mins() -> Vec<u64> {
+    vec![1, 5, 15]
+}
+
+fn default_propagation_window_mins() -> u64 {
+    15
+}
+
+fn default_contest_rate_window_mins() -> u64 {
+    10
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            activity_windows_mins: default_activity_windows_mins(),
+            propagation_window_mins: default_propagation_window_mins(),
+            contest_profile: None,
+            contest_rate_window_mins: default_contest_rate_window_mins(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BeaconConfig {
+    /// beacons besides the NCDXF ones and calls ending in /B
+    #[serde(default)]
+    pub calls: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct GeoConfig {
+    /// skimmer callsigns with their locator or latitude and longitude
+    #[serde(default)]
+    pub skimmer_file: Option<String>,
+    /// entity coordinates and prefixes in the cty.dat format
+    #[serde(default)]
+    pub country_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ActivationConfig {
+    /// CSV files of award program references: program,reference,name
+    #[serde(default)]
+    pub reference_files: Vec<String>,
+    /// JSON files of scheduled activations
+    #[serde(default)]
+    pub schedule_files: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbookConfig {
+    /// CSV file (call,name,grid,country) or http(s) URL of a callbook API
+    /// with {call} in it, no lookups if not set
+    #[serde(default)]
+    pub source: Option<String>,
+    /// lookups are kept this long
+    #[serde(default = "default_callbook_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+}
+
+fn default_callbook_cache_ttl_secs() -> u64 {
+    86400
+}
+
+impl Default for CallbookConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            cache_ttl_secs: default_callbook_cache_ttl_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpaceWeatherConfig {
+    /// file or http(s) URL of a report in the NOAA/WWV text format, not
+    /// polled if not set
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default = "default_space_weather_poll_period_secs")]
+    pub poll_period_secs: u64,
+}
+
+fn default_space_weather_poll_period_secs() -> u64 {
+    3600
+}
+
+impl Default for SpaceWeatherConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            poll_period_secs: default_space_weather_poll_period_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserConfig {
+    /// ADIF log of the user, for worked and confirmed entities
+    pub adif_file: String,
+    /// alert on needed calls on these bands and in these modes only, all
+    /// bands and modes if empty
+    #[serde(default)]
+    pub alert_bands: Vec<String>,
+    #[serde(default)]
+    pub alert_modes: Vec<String>,
+    /// skimmers that must hear a needed call before it is alerted
+    #[serde(default = "default_alert_min_skimmers")]
+    pub alert_min_skimmers: usize,
+}
+
+fn default_alert_min_skimmers() -> usize {
+    2
 }
 
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config, String> {
     let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
     let cfg: Config = serde_yaml::from_str(&text).map_err(|e| e.to_string())?;
+    validate(&cfg)?;
     Ok(cfg)
 }
+
+/// Checks that the windows of the API views fit into the spot retention.
+fn validate(cfg: &Config) -> Result<(), String> {
+    let max_mins = cfg.db.max_spot_age_secs / 60;
+    let api = &cfg.api;
+    if let Some(w) = api.activity_windows_mins.iter().find(|w| **w > max_mins) {
+        return Err(format!(
+            "api.activity_windows_mins: {w} minutes is longer than db.max_spot_age_secs"
+        ));
+    }
+    if api.propagation_window_mins.saturating_mul(2) > max_mins {
+        return Err(
+            "api.propagation_window_mins: twice the window is longer than db.max_spot_age_secs"
+                .to_string(),
+        );
+    }
+    if api.contest_rate_window_mins > max_mins {
+        return Err("api.contest_rate_window_mins is longer than db.max_spot_age_secs".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn shipped_config_is_valid() {
+        let cfg = load_config("config.yaml").unwrap();
+        assert!(cfg.api.activity_windows_mins.iter().all(|w| *w > 0));
+    }
+
+    #[rstest]
+    fn windows_beyond_retention() {
+        let mut cfg: Config = serde_yaml::from_str(
+            "rbn: {host: h, port: 1, callsign: c}\ndb: {cleanup_period_secs: 60, max_spot_age_secs: 1800}\nregion_file: r",
+        )
+        .unwrap();
+        assert!(validate(&cfg).is_ok());
+        cfg.api.activity_windows_mins = vec![5, 60];
+        assert!(validate(&cfg).is_err());
+    }
+}
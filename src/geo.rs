@@ -0,0 +1,305 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::{fs, path::Path as FsPath};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A position in degrees, north and east positive.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct LatLon {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Great-circle path between two stations, rounded to whole km and degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct Path {
+    pub distance_km: u32,
+    /// initial bearing, clockwise from true north
+    pub bearing_deg: u16,
+}
+
+impl LatLon {
+    /// Centre of a 4 or 6 character Maidenhead locator like "JN47" or "JN47pk".
+    pub fn from_locator(locator: &str) -> Option<Self> {
+        let chars: Vec<u8> = locator.to_ascii_uppercase().bytes().collect();
+        if chars.len() != 4 && chars.len() != 6 {
+            return None;
+        }
+        let field = |c: u8| (b'A'..=b'R').contains(&c).then(|| f64::from(c - b'A'));
+        let square = |c: u8| c.is_ascii_digit().then(|| f64::from(c - b'0'));
+        let subsquare = |c: u8| (b'A'..=b'X').contains(&c).then(|| f64::from(c - b'A'));
+        let mut lon = field(chars[0])? * 20.0 - 180.0 + square(chars[2])? * 2.0;
+        let mut lat = field(chars[1])? * 10.0 - 90.0 + square(chars[3])?;
+        if chars.len() == 6 {
+            lon += subsquare(chars[4])? * 2.0 / 24.0 + 1.0 / 24.0;
+            lat += subsquare(chars[5])? / 24.0 + 0.5 / 24.0;
+        } else {
+            lon += 1.0;
+            lat += 0.5;
+        }
+        Some(Self { lat, lon })
+    }
+
+    pub fn path_to(&self, other: &LatLon) -> Path {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        let distance_km = 2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin();
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        let bearing_deg = y.atan2(x).to_degrees().rem_euclid(360.0).round() as u16 % 360;
+        Path {
+            distance_km: distance_km.round() as u32,
+            bearing_deg,
+        }
+    }
+}
+
+/// Coordinates and CQ zone of a country file prefix or call.
+#[derive(Debug, Clone, Copy)]
+struct CtyEntry {
+    location: LatLon,
+    cq_zone: u8,
+}
+
+/// Where skimmers and spotted stations are: skimmers from a skimmer list,
+/// everybody else from the coordinates of their DXCC entity.
+#[derive(Debug, Default)]
+pub struct Locations {
+    skimmers: HashMap<String, LatLon>,
+    // entity prefix -> entity coordinates and zone, the longest prefix wins
+    prefixes: HashMap<String, CtyEntry>,
+    // calls listed on their own in the country file
+    exact_calls: HashMap<String, CtyEntry>,
+}
+
+/// Parses a cty.dat coordinate pair, whose longitude is west positive.
+fn parse_cty_lat_lon(lat: &str, lon: &str) -> Result<LatLon> {
+    Ok(LatLon {
+        lat: lat.trim().parse()?,
+        lon: -lon.trim().parse::<f64>()?,
+    })
+}
+
+impl Locations {
+    pub fn load_skimmers<P: AsRef<FsPath> + std::fmt::Display>(&mut self, path: P) -> Result<()> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        self.add_skimmers(&text)
+            .map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// Reads one skimmer per line, its callsign followed by either a
+    /// Maidenhead locator or latitude and longitude. `#` starts a comment.
+    pub fn add_skimmers(&mut self, text: &str) -> Result<()> {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .collect();
+            let location = match fields[..] {
+                [] => continue,
+                [_, locator] => LatLon::from_locator(locator),
+                [_, lat, lon] => lat
+                    .parse()
+                    .ok()
+                    .zip(lon.parse().ok())
+                    .map(|(lat, lon)| LatLon { lat, lon }),
+                _ => None,
+            };
+            let Some(location) = location else {
+                bail!("invalid skimmer line: {line}");
+            };
+            self.skimmers.insert(fields[0].to_uppercase(), location);
+        }
+        Ok(())
+    }
+
+    pub fn load_country_file<P: AsRef<FsPath> + std::fmt::Display>(
+        &mut self,
+        path: P,
+    ) -> Result<()> {
+        let text = fs::read_to_string(&path).map_err(|e| anyhow!("failed to read {path}: {e}"))?;
+        self.add_country_file(&text)
+            .map_err(|e| anyhow!("failed to read {path}: {e}"))
+    }
+
+    /// Reads entity coordinates, CQ zones and prefixes from a country file
+    /// in the cty.dat format. Coordinate overrides like `<35.0/-139.0>` and
+    /// CQ zone overrides like `(4)` are honoured, the others are ignored.
+    pub fn add_country_file(&mut self, text: &str) -> Result<()> {
+        for record in text.split(';') {
+            if record.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = record.splitn(9, ':').collect();
+            let [name, cq_zone, _, _, lat, lon, _, primary, aliases] = fields[..] else {
+                bail!("invalid country file record: {}", record.trim());
+            };
+            let entity = CtyEntry {
+                location: parse_cty_lat_lon(lat, lon)
+                    .map_err(|e| anyhow!("invalid coordinates of {}: {e}", name.trim()))?,
+                cq_zone: cq_zone
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow!("invalid CQ zone of {}: {e}", name.trim()))?,
+            };
+            let primary = primary.trim().trim_start_matches('*');
+            self.prefixes.insert(primary.to_string(), entity);
+            for alias in aliases.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+                let location = match alias.split_once('<') {
+                    Some((_, rest)) => {
+                        let coords = rest.split('>').next().unwrap_or_default();
+                        let (lat, lon) = coords
+                            .split_once('/')
+                            .ok_or_else(|| anyhow!("invalid override in {alias}"))?;
+                        parse_cty_lat_lon(lat, lon)?
+                    }
+                    None => entity.location,
+                };
+                let cq_zone = match alias.split_once('(') {
+                    Some((_, rest)) => rest
+                        .split(')')
+                        .next()
+                        .unwrap_or_default()
+                        .parse()
+                        .map_err(|e| anyhow!("invalid CQ zone override in {alias}: {e}"))?,
+                    None => entity.cq_zone,
+                };
+                let entry = CtyEntry { location, cq_zone };
+                let call = alias
+                    .split(['(', '[', '<', '{', '~'])
+                    .next()
+                    .unwrap_or_default();
+                match call.strip_prefix('=') {
+                    Some(call) => self.exact_calls.insert(call.to_string(), entry),
+                    None => self.prefixes.insert(call.to_string(), entry),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    // exact call, else the longest prefix
+    fn cty_entry(&self, callsign: &str) -> Option<&CtyEntry> {
+        if let Some(entry) = self.exact_calls.get(callsign) {
+            return Some(entry);
+        }
+        (1..=callsign.len())
+            .rev()
+            .filter(|n| callsign.is_char_boundary(*n))
+            .find_map(|n| self.prefixes.get(&callsign[..n]))
+    }
+
+    /// Coordinates of the entity of `callsign`.
+    pub fn station(&self, callsign: &str) -> Option<LatLon> {
+        self.cty_entry(callsign).map(|e| e.location)
+    }
+
+    /// CQ zone of `callsign` from the country file.
+    pub fn cq_zone(&self, callsign: &str) -> Option<u8> {
+        self.cty_entry(callsign).map(|e| e.cq_zone)
+    }
+
+    /// Location of a skimmer from the skimmer list, else of its entity.
+    pub fn skimmer(&self, callsign: &str) -> Option<LatLon> {
+        self.skimmers
+            .get(callsign)
+            .copied()
+            .or_else(|| self.station(callsign))
+    }
+
+    /// Path from `spotter` to `spotted`, `None` if either is unknown.
+    pub fn path(&self, spotter: &str, spotted: &str) -> Option<Path> {
+        Some(self.skimmer(spotter)?.path_to(&self.station(spotted)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const CTY: &str = "\
+Fed. Rep. of Germany:     14:  28:  EU:   51.00:   -10.00:    -1.0:  DL:
+    DA,DB,DC,DD,DE,DF,DG,DH,DI,DJ,DK,DL,DM,DN,DO,DP,DQ,DR,Y2,Y3,Y4,Y5,Y6,Y7,Y8,Y9;
+United States:            05:  08:  NA:   37.53:    91.67:     5.0:  K:
+    AA,AB,K,N,W,=W1AW(5)[8]<41.71/72.73>,
+    KH6(31)<21.12/157.48>,K0(4);
+";
+
+    #[rstest]
+    #[case("JN47", 47.5, 9.0)]
+    #[case("jo62qm", 52.52, 13.375)]
+    #[case("AA00", -89.5, -179.0)]
+    fn locator_centre(#[case] locator: &str, #[case] lat: f64, #[case] lon: f64) {
+        let l = LatLon::from_locator(locator).unwrap();
+        assert!((l.lat - lat).abs() < 0.01, "{l:?}");
+        assert!((l.lon - lon).abs() < 0.01, "{l:?}");
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("JN4")]
+    #[case("ZZ99")]
+    #[case("JN47zz")]
+    fn invalid_locator(#[case] locator: &str) {
+        assert_eq!(LatLon::from_locator(locator), None);
+    }
+
+    #[rstest]
+    // Berlin -> New York
+    #[case((52.52, 13.40), (40.71, -74.01), 6385, 296)]
+    #[case((0.0, 0.0), (0.0, 90.0), 10008, 90)]
+    #[case((10.0, 0.0), (-10.0, 0.0), 2224, 180)]
+    fn great_circle(
+        #[case] from: (f64, f64),
+        #[case] to: (f64, f64),
+        #[case] distance_km: u32,
+        #[case] bearing_deg: u16,
+    ) {
+        let from = LatLon {
+            lat: from.0,
+            lon: from.1,
+        };
+        let to = LatLon {
+            lat: to.0,
+            lon: to.1,
+        };
+        let path = from.path_to(&to);
+        assert!(path.distance_km.abs_diff(distance_km) <= 1, "{path:?}");
+        assert_eq!(path.bearing_deg, bearing_deg);
+    }
+
+    #[rstest]
+    fn locate_stations() {
+        let mut locations = Locations::default();
+        locations.add_country_file(CTY).unwrap();
+        locations
+            .add_skimmers("# call locator\nDL1AAA JO62qm\nW3LPL, 39.2, -77.3\n\n")
+            .unwrap();
+
+        assert_eq!(locations.station("DL2XYZ").unwrap().lat, 51.0);
+        assert_eq!(locations.station("K1ABC").unwrap().lon, -91.67);
+        assert_eq!(locations.station("KH6ABC").unwrap().lat, 21.12);
+        assert_eq!(locations.station("W1AW").unwrap().lat, 41.71);
+        assert_eq!(locations.station("JA1ABC"), None);
+        assert_eq!(locations.cq_zone("DL2XYZ"), Some(14));
+        assert_eq!(locations.cq_zone("K1ABC"), Some(5));
+        assert_eq!(locations.cq_zone("K0ABC"), Some(4));
+        assert_eq!(locations.cq_zone("KH6ABC"), Some(31));
+        assert_eq!(locations.cq_zone("JA1ABC"), None);
+        // skimmers not on the list are placed in their entity
+        assert!((locations.skimmer("DL1AAA").unwrap().lat - 52.52).abs() < 0.01);
+        assert_eq!(locations.skimmer("DL9ZZZ").unwrap().lat, 51.0);
+        let path = locations.path("W3LPL", "DL2XYZ").unwrap();
+        assert!((6000..7500).contains(&path.distance_km));
+        assert!(locations.path("DL1AAA", "JA1ABC").is_none());
+
+        assert!(locations.add_skimmers("DL1AAA XX").is_err());
+        assert!(locations.add_country_file("Nowhere: 1: 2;").is_err());
+    }
+}
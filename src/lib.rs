@@ -0,0 +1,15 @@
+pub mod activations;
+pub mod adif;
+pub mod bands;
+pub mod beacons;
+pub mod callbook;
+pub mod config;
+pub mod geo;
+pub mod line_source;
+pub mod rbn_reader;
+pub mod region_loader;
+pub mod rest_api;
+pub mod shared;
+pub mod solar;
+pub mod space_weather;
+pub mod spot_db;
@@ -1,21 +1,17 @@
 use anyhow::Result;
 use clap::Parser;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::path::PathBuf;
 use std::process;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::spawn;
 
-use rest_api::serve;
-
-mod bands;
-mod config;
-mod line_source;
-mod rbn_reader;
-mod region_loader;
-mod rest_api;
-mod shared;
-mod spot_db;
+use ham_activity::rest_api::serve;
+use ham_activity::{
+    activations, adif, bands, callbook, config, geo, rbn_reader, region_loader, shared,
+    space_weather, spot_db,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -37,51 +33,51 @@ async fn periodic_cleaner(shared_db: spot_db::SharedDB, db_cfg: config::DBConfig
     loop {
         tokio::time::sleep(cleanup_period).await;
         info!("running periodic cleaner");
-        let mut db = shared_db.write();
-        db.cleanup_old_spots(max_spot_age);
+        shared_db
+            .update(move |db| db.cleanup_old_spots(max_spot_age))
+            .await;
         info!("finished periodic cleaner");
     }
 }
-fn load_regions(shared_db: &spot_db::SharedDB, regions: &[region_loader::Dxcc]) {
-    let mut db = shared_db.write();
-    for region in regions {
-        // any dxcc that has validEnd set is not relevant for us
-        if !region.valid_end.is_empty() {
-            continue;
-        }
-        let prefixes: Vec<String> = region
-            .prefix
-            .split(',')
-            .map(std::string::ToString::to_string)
-            .collect();
-        db.add_region(
-            region.name.to_lowercase().replace(char::is_whitespace, "_"),
-            prefixes.clone(),
-        );
-        for cq in region.cq.clone() {
-            let cq_name = format!("CQ_{cq}");
-            match db.get_region(&cq_name) {
-                Some(r) => {
-                    let mut new_prefixes = prefixes.clone();
-                    new_prefixes.append(&mut r.prefixes.clone());
-                    db.add_region(cq_name, new_prefixes);
-                }
-                None => db.add_region(cq_name, prefixes.clone()),
-            }
-        }
-        for continent in region.continent.clone() {
-            match db.get_region(&continent) {
-                Some(r) => {
-                    let mut new_prefixes = prefixes.clone();
-                    new_prefixes.append(&mut r.prefixes.clone());
-                    db.add_region(continent, new_prefixes);
-                }
-                None => db.add_region(continent, prefixes.clone()),
+async fn periodic_calibration(shared_db: spot_db::SharedDB, period_secs: u64) {
+    let period = Duration::from_secs(period_secs);
+    loop {
+        tokio::time::sleep(period).await;
+        debug!("updating skimmer calibration");
+        // estimate under the read lock, ingestion only waits for the swap
+        let calibration = shared_db.query(|db| db.estimate_calibration()).await;
+        shared_db
+            .update(move |db| db.set_calibration(calibration))
+            .await;
+    }
+}
+
+async fn periodic_space_weather(
+    shared_db: spot_db::SharedDB,
+    provider: Arc<dyn space_weather::SpaceWeatherProvider>,
+    period_secs: u64,
+) {
+    let period = Duration::from_secs(period_secs);
+    loop {
+        let p = provider.clone();
+        match tokio::task::spawn_blocking(move || p.fetch()).await {
+            Ok(Ok(weather)) => {
+                debug!("space weather: {weather:?}");
+                shared_db
+                    .update(move |db| db.add_space_weather(weather))
+                    .await;
             }
+            Ok(Err(e)) => warn!("could not get space weather: {e}"),
+            Err(e) => error!("space weather provider panicked: {e}"),
         }
+        tokio::time::sleep(period).await;
     }
 }
 
+fn load_regions(shared_db: &spot_db::SharedDB, regions: &[region_loader::Dxcc]) {
+    shared_db.write().add_dxcc_regions(regions);
+}
+
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let cli = Cli::parse();
@@ -99,12 +95,67 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     env_logger::init();
     debug!("{cfg:#?}");
 
-    let shared_db = shared::Shared::new(spot_db::SpotDB::new());
+    let band_plan = match &cfg.band_plan.file {
+        Some(file) => bands::BandPlan::load(file, cfg.band_plan.iaru_region)?,
+        None => bands::BandPlan::builtin(cfg.band_plan.iaru_region)?,
+    };
+    let mut db = spot_db::SpotDB::with_band_plan(band_plan);
+    db.set_max_spots(cfg.db.max_spots);
+    db.set_max_spot_age(Duration::from_secs(cfg.db.max_spot_age_secs));
+    db.set_beacon_calls(cfg.beacons.calls);
+    let mut locations = geo::Locations::default();
+    if let Some(file) = &cfg.geo.skimmer_file {
+        locations.load_skimmers(file)?;
+    }
+    if let Some(file) = &cfg.geo.country_file {
+        locations.load_country_file(file)?;
+    }
+    db.set_locations(locations);
+    let mut activations = activations::Activations::default();
+    for file in &cfg.activations.reference_files {
+        activations.load_references(file)?;
+    }
+    for file in &cfg.activations.schedule_files {
+        activations.load_schedule(file)?;
+    }
+    db.set_activations(activations);
+    let shared_db = shared::Shared::new(db);
     let regions = region_loader::load(cfg.region_file)?;
 
     load_regions(&shared_db.clone(), &regions);
+    // entities of the logged QSOs come from the regions
+    for (user, user_cfg) in &cfg.users {
+        let qsos = adif::load(&user_cfg.adif_file)?;
+        info!("loaded {} QSOs of {user}", qsos.len());
+        let preferences = spot_db::AlertPreferences {
+            bands: user_cfg.alert_bands.clone(),
+            modes: user_cfg.alert_modes.clone(),
+            min_skimmers: user_cfg.alert_min_skimmers,
+        };
+        let mut db = shared_db.write();
+        db.set_user_log(user.clone(), &qsos);
+        db.set_alert_preferences(user.clone(), preferences);
+    }
 
-    spawn(serve(shared_db.clone()));
+    let callbook = match &cfg.callbook.source {
+        Some(source) => Some(Arc::new(callbook::CallbookCache::new(
+            callbook::provider(source)?,
+            Duration::from_secs(cfg.callbook.cache_ttl_secs),
+        ))),
+        None => None,
+    };
+    spawn(serve(shared_db.clone(), cfg.api.clone(), callbook));
+    spawn(periodic_calibration(
+        shared_db.clone(),
+        cfg.db.calibration_period_secs,
+    ));
+    if let Some(source) = &cfg.space_weather.source {
+        spawn(periodic_space_weather(
+            shared_db.clone(),
+            space_weather::provider(source),
+            cfg.space_weather.poll_period_secs,
+        ));
+    }
     spawn(periodic_cleaner(shared_db.clone(), cfg.db));
     rbn_reader::read_rbn(shared_db.clone(), cfg.rbn).await?;
 
@@ -82,7 +82,7 @@ fn parse_spot_split(line: &str) -> Result<SpotInfo> {
     get_part(Some("WPM"))?;
     let msg = get_part(None)?.to_string();
     if msg == "NCDXF" {
-        // B probably means beacon?
+        // "NCDXF B": a beacon of the NCDXF/IBP network
         let _ = get_part(Some("B"));
     }
     let hhmmz = get_part(None)?.to_string();
@@ -129,11 +129,14 @@ async fn connect_read(shared_db: SharedDB, cfg: &config::RBNConfig) -> Result<()
                 match parse_spot_split(line) {
                     Ok(s) => {
                         trace!("parsed: {line}");
-                        let mut db = shared_db.write();
-                        db.add_spot(
-                            &s.spotter, &s.spotted, s.freq, &s.mode, s.snr_db, s.wpm, &s.msg,
-                            s.utc_time,
-                        );
+                        shared_db
+                            .update(move |db| {
+                                db.add_spot(
+                                    &s.spotter, &s.spotted, s.freq, &s.mode, s.snr_db, s.wpm,
+                                    &s.msg, s.utc_time,
+                                );
+                            })
+                            .await;
                     }
                     Err(e) => {
                         info!("could not parse line: {line}, {e}");
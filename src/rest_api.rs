@@ -1,5 +1,15 @@
+mod alerts;
+mod bandmap;
+mod beacons;
+mod call;
+mod contest;
+mod diagnostics;
 mod filters;
+mod greyline;
+mod propagation;
 mod region;
+mod signals;
+mod spotter;
 mod stats;
 
 pub use self::filters::serve;
@@ -0,0 +1,27 @@
+use log::debug;
+
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AlertsQuery {
+    pub user: String,
+    /// only alerts after this one, to poll for new alerts
+    #[serde(default)]
+    pub since_id: u64,
+}
+
+pub async fn get_alerts(
+    query: AlertsQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_alerts");
+    let alerts = shared_db
+        .query(move |db| db.get_alerts(&query.user, query.since_id))
+        .await;
+    match alerts {
+        Some(alerts) => Ok(warp::reply::json(&alerts)),
+        None => Err(warp::reject::not_found()),
+    }
+}
@@ -0,0 +1,34 @@
+use log::debug;
+use uom::si::f64::Frequency;
+use uom::si::frequency::hertz;
+
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MIN_GAP_HZ: u64 = 500;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BandMapQuery {
+    /// only use spots of skimmers in this region
+    pub region: Option<String>,
+    /// minimum width of a reported clear gap
+    pub min_gap_hz: Option<u64>,
+}
+
+pub async fn get_band_map(
+    band: String,
+    query: BandMapQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_band_map");
+    let min_gap_hz = query.min_gap_hz.unwrap_or(DEFAULT_MIN_GAP_HZ);
+    let min_gap = Frequency::new::<hertz>(min_gap_hz as f64);
+    let band_map = shared_db
+        .query(move |db| db.get_band_map(&band, query.region.as_deref(), min_gap))
+        .await;
+    match band_map {
+        Some(band_map) => Ok(warp::reply::json(&band_map)),
+        None => Err(warp::reject::not_found()),
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::Utc;
+use log::debug;
+
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BeaconsQuery {
+    /// only use skimmers of this region
+    pub region: Option<String>,
+}
+
+pub async fn get_beacons(
+    query: BeaconsQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_beacons");
+    let beacons = shared_db
+        .query(move |db| db.get_beacons(query.region.as_deref(), Utc::now()))
+        .await;
+    match beacons {
+        Some(beacons) => Ok(warp::reply::json(&beacons)),
+        None => Err(warp::reject::not_found()),
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::Utc;
+use log::debug;
+
+use crate::activations::{ProgramReference, parse_programs};
+use crate::spot_db;
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CallQuery {
+    /// comma separated award programs, e.g. POTA,SOTA: only spots activating
+    /// a reference of them
+    pub program: Option<String>,
+}
+
+pub async fn get_call(
+    callsign: String,
+    query: CallQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_call");
+    let callsign = callsign.to_uppercase();
+    let programs = match &query.program {
+        Some(p) => parse_programs(p).ok_or_else(warp::reject::not_found)?,
+        None => Vec::new(),
+    };
+    let (bands, signals, references) = {
+        let filter = spot_db::SignalFilter {
+            callsign: Some(callsign.clone()),
+            programs,
+            ..Default::default()
+        };
+        shared_db
+            .query(move |db| {
+                let callsign = filter.callsign.as_deref().unwrap_or_default();
+                let references = db
+                    .get_activations(callsign, Utc::now())
+                    .into_iter()
+                    .filter(|r| filter.programs.is_empty() || filter.programs.contains(&r.program))
+                    .collect();
+                (
+                    db.get_call_activity(callsign, &filter.programs),
+                    db.get_signals(&filter),
+                    references,
+                )
+            })
+            .await
+    };
+    let num_spots = bands
+        .iter()
+        .flat_map(|b| b.skimmers.iter())
+        .map(|s| s.num_spots)
+        .sum();
+    Ok(warp::reply::json(&Call {
+        callsign,
+        num_spots,
+        num_signals: signals.len(),
+        references,
+        bands,
+        signals,
+    }))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Call {
+    pub callsign: String,
+    pub num_spots: usize,
+    pub num_signals: usize,
+    /// references the station is scheduled to activate now
+    pub references: Vec<ProgramReference>,
+    pub bands: Vec<spot_db::CallBandActivity>,
+    pub signals: Vec<spot_db::Signal>,
+}
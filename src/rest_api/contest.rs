@@ -0,0 +1,53 @@
+use chrono::Utc;
+use log::debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::ApiConfig;
+use crate::spot_db::{ContestProfile, SharedDB};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ContestQuery {
+    /// cqww or wpx, overrides the configured profile
+    pub profile: Option<String>,
+    /// count the calls per hour over this many minutes
+    pub rate_window_mins: Option<u64>,
+}
+
+pub async fn get_contest(
+    region: String,
+    query: ContestQuery,
+    shared_db: SharedDB,
+    cfg: Arc<ApiConfig>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_contest");
+    let profile = match &query.profile {
+        Some(p) => ContestProfile::parse(p),
+        None => cfg.contest_profile,
+    }
+    .ok_or_else(warp::reject::not_found)?;
+    let rate_window = query
+        .rate_window_mins
+        .unwrap_or(cfg.contest_rate_window_mins)
+        .checked_mul(60)
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(warp::reject::not_found)?;
+    // only hold the database lock to look up what the view is computed from
+    let snapshot = shared_db
+        .query(move |db| {
+            // no spots are kept beyond the retention to count the rate over
+            if rate_window > db.max_spot_age() {
+                return None;
+            }
+            db.contest_snapshot(profile, &region, rate_window, Utc::now())
+        })
+        .await
+        .ok_or_else(warp::reject::not_found)?;
+    let contest = tokio::task::spawn_blocking(move || snapshot.contest())
+        .await
+        .expect("contest view panicked");
+    Ok(warp::reply::json(&contest))
+}
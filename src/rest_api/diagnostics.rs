@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use log::debug;
+use std::collections::HashMap;
+use uom::si::frequency::kilohertz;
+
+use crate::bands::SegmentKind;
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OutOfBandSpot {
+    pub spotter: String,
+    pub spotted: String,
+    pub frequency: f64, // kHz
+    pub snr_db: i32,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OutOfBand {
+    pub num_spots: usize,
+    /// number of out of band spots per skimmer, to find misbehaving ones
+    pub spots_per_spotter: HashMap<String, usize>,
+    pub spots: Vec<OutOfBandSpot>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OutOfSegmentSpot {
+    pub spotter: String,
+    pub spotted: String,
+    pub frequency: f64, // kHz
+    pub mode: String,
+    /// segment the spot is in, `None` if in none
+    pub segment: Option<SegmentKind>,
+    pub snr_db: i32,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OutOfSegment {
+    pub num_spots: usize,
+    /// number of out of segment spots per skimmer
+    pub spots_per_spotter: HashMap<String, usize>,
+    pub spots: Vec<OutOfSegmentSpot>,
+}
+
+fn count_per_spotter<'a>(spotters: impl Iterator<Item = &'a String>) -> HashMap<String, usize> {
+    let mut spots_per_spotter = HashMap::new();
+    for spotter in spotters {
+        *spots_per_spotter.entry(spotter.clone()).or_insert(0) += 1;
+    }
+    spots_per_spotter
+}
+
+pub async fn get_out_of_band(shared_db: SharedDB) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_out_of_band");
+    let mut spots: Vec<OutOfBandSpot> = shared_db
+        .query(|db| {
+            db.out_of_band_spots()
+                .iter()
+                .map(|s| OutOfBandSpot {
+                    spotter: s.spotter.clone(),
+                    spotted: s.spotted.clone(),
+                    frequency: s.freq.get::<kilohertz>(),
+                    snr_db: s.snr_db,
+                    timestamp: s.timestamp,
+                })
+                .collect()
+        })
+        .await;
+    let spots_per_spotter = count_per_spotter(spots.iter().map(|s| &s.spotter));
+    spots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    Ok(warp::reply::json(&OutOfBand {
+        num_spots: spots.len(),
+        spots_per_spotter,
+        spots,
+    }))
+}
+
+pub async fn get_out_of_segment(shared_db: SharedDB) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_out_of_segment");
+    let mut spots: Vec<OutOfSegmentSpot> = shared_db
+        .query(|db| {
+            db.out_of_segment_spots()
+                .into_iter()
+                .map(|s| OutOfSegmentSpot {
+                    spotter: s.spotter.clone(),
+                    spotted: s.spotted.clone(),
+                    frequency: s.freq.get::<kilohertz>(),
+                    mode: s.mode.clone(),
+                    segment: s.segment,
+                    snr_db: s.snr_db,
+                    timestamp: s.timestamp,
+                })
+                .collect()
+        })
+        .await;
+    let spots_per_spotter = count_per_spotter(spots.iter().map(|s| &s.spotter));
+    spots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    Ok(warp::reply::json(&OutOfSegment {
+        num_spots: spots.len(),
+        spots_per_spotter,
+        spots,
+    }))
+}
@@ -1,18 +1,129 @@
-use super::region::{get_frequency, get_region, get_regions};
+use super::alerts::{AlertsQuery, get_alerts};
+use super::bandmap::{BandMapQuery, get_band_map};
+use super::beacons::{BeaconsQuery, get_beacons};
+use super::call::{CallQuery, get_call};
+use super::contest::{ContestQuery, get_contest};
+use super::diagnostics::{get_out_of_band, get_out_of_segment};
+use super::greyline::{GreylineQuery, get_greyline};
+use super::propagation::{PropagationQuery, get_propagation};
+use super::region::{FrequencyQuery, RegionQuery, get_frequency, get_region, get_regions};
+use super::signals::{SignalsQuery, get_signals};
+use super::spotter::{SpottersQuery, get_spotter, get_spotters};
 use super::stats::get_db_stats;
+use crate::callbook::CallbookCache;
+use crate::config::ApiConfig;
 use crate::spot_db::SharedDB;
 use log::info;
+use std::sync::Arc;
 use warp::Filter;
 
 fn get_frequency_route(
     db: SharedDB,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    warp::path!("frequency" / u64)
+    warp::path!("frequency" / String)
         .and(warp::get())
+        .and(warp::query::<FrequencyQuery>())
         .and(with_db(db))
         .and_then(get_frequency)
 }
 
+fn get_call_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("call" / String)
+        .and(warp::get())
+        .and(warp::query::<CallQuery>())
+        .and(with_db(db))
+        .and_then(get_call)
+}
+
+fn get_spotter_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("spotter" / String)
+        .and(warp::get())
+        .and(with_db(db))
+        .and_then(get_spotter)
+}
+
+fn get_spotters_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("spotters")
+        .and(warp::get())
+        .and(warp::query::<SpottersQuery>())
+        .and(with_db(db))
+        .and_then(get_spotters)
+}
+
+fn get_band_map_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("bandmap" / String)
+        .and(warp::get())
+        .and(warp::query::<BandMapQuery>())
+        .and(with_db(db))
+        .and_then(get_band_map)
+}
+
+fn get_beacons_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("beacons")
+        .and(warp::get())
+        .and(warp::query::<BeaconsQuery>())
+        .and(with_db(db))
+        .and_then(get_beacons)
+}
+
+fn get_alerts_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("alerts")
+        .and(warp::get())
+        .and(warp::query::<AlertsQuery>())
+        .and(with_db(db))
+        .and_then(get_alerts)
+}
+
+fn get_greyline_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("greyline")
+        .and(warp::get())
+        .and(warp::query::<GreylineQuery>())
+        .and(with_db(db))
+        .and_then(get_greyline)
+}
+
+fn get_signals_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("signals")
+        .and(warp::get())
+        .and(warp::query::<SignalsQuery>())
+        .and(with_db(db))
+        .and_then(get_signals)
+}
+
+fn get_out_of_band_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("diagnostics" / "out_of_band")
+        .and(warp::get())
+        .and(with_db(db))
+        .and_then(get_out_of_band)
+}
+
+fn get_out_of_segment_route(
+    db: SharedDB,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("diagnostics" / "out_of_segment")
+        .and(warp::get())
+        .and(with_db(db))
+        .and_then(get_out_of_segment)
+}
+
 fn get_regions_route(
     db: SharedDB,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -24,13 +135,42 @@ fn get_regions_route(
 
 fn get_region_route(
     db: SharedDB,
+    cfg: Arc<ApiConfig>,
+    callbook: Option<Arc<CallbookCache>>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path!("region" / String)
         .and(warp::get())
+        .and(warp::query::<RegionQuery>())
         .and(with_db(db))
+        .and(with_config(cfg))
+        .and(with_callbook(callbook))
         .and_then(get_region)
 }
 
+fn get_propagation_route(
+    db: SharedDB,
+    cfg: Arc<ApiConfig>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("propagation" / String)
+        .and(warp::get())
+        .and(warp::query::<PropagationQuery>())
+        .and(with_db(db))
+        .and(with_config(cfg))
+        .and_then(get_propagation)
+}
+
+fn get_contest_route(
+    db: SharedDB,
+    cfg: Arc<ApiConfig>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("contest" / String)
+        .and(warp::get())
+        .and(warp::query::<ContestQuery>())
+        .and(with_db(db))
+        .and(with_config(cfg))
+        .and_then(get_contest)
+}
+
 fn get_db_stats_route(
     db: SharedDB,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -42,12 +182,26 @@ fn get_db_stats_route(
 
 fn routes(
     db: SharedDB,
+    cfg: Arc<ApiConfig>,
+    callbook: Option<Arc<CallbookCache>>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     let fs = warp::path("ui").and(warp::fs::dir("./static/ui/"));
-    get_region_route(db.clone())
+    get_region_route(db.clone(), cfg.clone(), callbook)
         .or(get_db_stats_route(db.clone()))
         .or(get_regions_route(db.clone()))
         .or(get_frequency_route(db.clone()))
+        .or(get_call_route(db.clone()))
+        .or(get_spotter_route(db.clone()))
+        .or(get_spotters_route(db.clone()))
+        .or(get_band_map_route(db.clone()))
+        .or(get_signals_route(db.clone()))
+        .or(get_beacons_route(db.clone()))
+        .or(get_greyline_route(db.clone()))
+        .or(get_alerts_route(db.clone()))
+        .or(get_propagation_route(db.clone(), cfg.clone()))
+        .or(get_contest_route(db.clone(), cfg.clone()))
+        .or(get_out_of_band_route(db.clone()))
+        .or(get_out_of_segment_route(db.clone()))
         .or(fs)
 }
 
@@ -57,8 +211,21 @@ fn with_db(
     warp::any().map(move || db.clone())
 }
 
-pub async fn serve(db: SharedDB) {
-    let routes = routes(db);
+fn with_callbook(
+    callbook: Option<Arc<CallbookCache>>,
+) -> impl Filter<Extract = (Option<Arc<CallbookCache>>,), Error = std::convert::Infallible> + Clone
+{
+    warp::any().map(move || callbook.clone())
+}
+
+fn with_config(
+    cfg: Arc<ApiConfig>,
+) -> impl Filter<Extract = (Arc<ApiConfig>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || cfg.clone())
+}
+
+pub async fn serve(db: SharedDB, cfg: ApiConfig, callbook: Option<Arc<CallbookCache>>) {
+    let routes = routes(db, Arc::new(cfg), callbook);
     info!("Server started at http://0.0.0.0:8000");
     warp::serve(routes).run(([0, 0, 0, 0], 8000)).await;
 }
@@ -0,0 +1,42 @@
+use chrono::Utc;
+use log::debug;
+use std::time::Duration;
+
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MAX_AGE_MINS: u64 = 15;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct GreylineQuery {
+    /// only use skimmers of this region
+    pub region: Option<String>,
+    /// paths with spots within this many minutes are active
+    pub max_age_mins: Option<u64>,
+}
+
+pub async fn get_greyline(
+    query: GreylineQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_greyline");
+    let max_age = query
+        .max_age_mins
+        .unwrap_or(DEFAULT_MAX_AGE_MINS)
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(warp::reject::not_found)?;
+    let greyline = shared_db
+        .query(move |db| {
+            if max_age > db.max_spot_age() {
+                return None;
+            }
+            db.get_greyline(query.region.as_deref(), max_age, Utc::now())
+        })
+        .await;
+    match greyline {
+        Some(greyline) => Ok(warp::reply::json(&greyline)),
+        None => Err(warp::reject::not_found()),
+    }
+}
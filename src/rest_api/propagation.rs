@@ -0,0 +1,46 @@
+use chrono::Utc;
+use log::debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::ApiConfig;
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PropagationQuery {
+    /// compare the last this many minutes to the same time before
+    pub window_mins: Option<u64>,
+}
+
+pub async fn get_propagation(
+    region: String,
+    query: PropagationQuery,
+    shared_db: SharedDB,
+    cfg: Arc<ApiConfig>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_propagation");
+    let window = query
+        .window_mins
+        .unwrap_or(cfg.propagation_window_mins)
+        .checked_mul(60)
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(warp::reject::not_found)?;
+    // only hold the database lock to look up what the view is computed from
+    let snapshot = shared_db
+        .query(move |db| {
+            // the window before must still be within the spot retention
+            if window.checked_mul(2).is_none_or(|w| w > db.max_spot_age()) {
+                return None;
+            }
+            db.propagation_snapshot(&region, window, Utc::now())
+        })
+        .await
+        .ok_or_else(warp::reject::not_found)?;
+    let propagation = tokio::task::spawn_blocking(move || snapshot.propagation())
+        .await
+        .expect("propagation view panicked");
+    Ok(warp::reply::json(&propagation))
+}
@@ -1,48 +1,234 @@
 use log::debug;
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use uom::si::f64::Frequency;
 use uom::si::frequency::hertz;
 
+use crate::activations::parse_programs;
+use crate::callbook::CallbookCache;
+use crate::config::ApiConfig;
 use crate::spot_db;
 use crate::spot_db::SharedDB;
 
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RegionQuery {
+    /// comma separated activity windows in minutes, e.g. 2,10,30,60
+    pub windows: Option<String>,
+    /// also list calls flagged as probably busted decodes
+    #[serde(default)]
+    pub include_busted: bool,
+    /// comma separated list of modes, e.g. CW,FT8
+    pub mode: Option<String>,
+    /// only spots over at least / at most this skimmer to station distance
+    pub min_distance_km: Option<u32>,
+    pub max_distance_km: Option<u32>,
+    /// annotate the calls with their worked status in the log of this user
+    pub user: Option<String>,
+    /// comma separated award programs, e.g. POTA,SOTA: only calls activating
+    /// a reference of them
+    pub program: Option<String>,
+    /// add name, grid and country from the callbook to call_info
+    #[serde(default)]
+    pub enrich: bool,
+}
+
+/// Sorted, deduplicated activity windows from minutes. `None` if a window
+/// does not fit a `Duration`.
+fn windows_from_mins(mut mins: Vec<u64>) -> Option<Vec<Duration>> {
+    mins.sort_unstable();
+    mins.dedup();
+    mins.into_iter()
+        .map(|m| m.checked_mul(60).map(Duration::from_secs))
+        .collect()
+}
+
+/// Sorted `windows` with those longer than `max` shortened to it, spots
+/// older than the retention are gone anyway.
+fn clamp_windows(windows: Vec<Duration>, max: Duration) -> Vec<Duration> {
+    let mut windows: Vec<Duration> = windows.into_iter().map(|w| w.min(max)).collect();
+    windows.dedup();
+    windows
+}
+
+/// Parses "cw,FT8" into sorted, deduplicated, upper case modes.
+fn parse_modes(modes: &str) -> Vec<String> {
+    let mut modes: Vec<String> = modes
+        .split(',')
+        .map(|m| m.trim().to_uppercase())
+        .filter(|m| !m.is_empty())
+        .collect();
+    modes.sort_unstable();
+    modes.dedup();
+    modes
+}
+
+/// Parses "2,10,30" into activity windows. `None` if malformed.
+fn parse_windows(windows: &str) -> Option<Vec<Duration>> {
+    let mins = windows
+        .split(',')
+        .map(|w| w.trim().parse::<u64>().ok().filter(|m| *m > 0))
+        .collect::<Option<Vec<u64>>>()?;
+    windows_from_mins(mins)
+}
+
 pub async fn get_region(
     name: String,
+    query: RegionQuery,
     shared_db: SharedDB,
+    cfg: Arc<ApiConfig>,
+    callbook: Option<Arc<CallbookCache>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     debug!("--> get_db_region");
-    let db = shared_db.read();
-    if let Some(r) = db.get_region(&name) {
-        let (band_activities, spotters, call_info) = r.get_band_activities();
-        let region = Region {
-            name,
-            spotters,
-            num_spotter_spots: r.spotter_spots.len(),
-            band_activities,
-            call_info,
-        };
-        Ok(warp::reply::json(&region))
-    } else {
-        Err(warp::reject::not_found())
+    let windows = match &query.windows {
+        Some(w) => parse_windows(w),
+        None => windows_from_mins(cfg.activity_windows_mins.clone()),
+    }
+    .ok_or_else(warp::reject::not_found)?;
+    if let (Some(min), Some(max)) = (query.min_distance_km, query.max_distance_km)
+        && min > max
+    {
+        return Err(warp::reject::not_found());
+    }
+    let programs = match &query.program {
+        Some(p) => parse_programs(p).ok_or_else(warp::reject::not_found)?,
+        None => Vec::new(),
+    };
+    // only hold the database lock to look up the region, its activity is
+    // computed under the region's own lock
+    let user = query.user.clone();
+    let callbook = callbook.filter(|_| query.enrich);
+    let mut query = spot_db::ActivityQuery {
+        windows,
+        include_busted: query.include_busted,
+        modes: query.mode.as_deref().map(parse_modes).unwrap_or_default(),
+        min_distance_km: query.min_distance_km,
+        max_distance_km: query.max_distance_km,
+        programs,
+    };
+    let Some((region, band_plan, busted, max_spot_age, worked)) = shared_db
+        .query(move |db| {
+            // the user's log and what locates the calls, for the worked status
+            let worked = match user {
+                Some(user) => {
+                    let log = db.get_user_log(&user)?.clone();
+                    Some((user, log, db.locator()))
+                }
+                None => None,
+            };
+            db.get_region(&name).map(|r| {
+                (
+                    r.clone(),
+                    db.band_plan().clone(),
+                    db.busted_calls(),
+                    db.max_spot_age(),
+                    worked,
+                )
+            })
+        })
+        .await
+    else {
+        return Err(warp::reject::not_found());
+    };
+    query.windows = clamp_windows(query.windows, max_spot_age);
+    let mut region = tokio::task::spawn_blocking(move || {
+        let activity = region.get_band_activities(&band_plan, &query, &busted);
+        let user_status =
+            worked.map(|(user, log, locator)| log.user_status(&user, &activity, &locator));
+        Region {
+            name: region.name.clone(),
+            num_spotter_spots: region.num_spots(),
+            num_signals: region.num_signals(),
+            activity,
+            user_status,
+        }
+    })
+    .await
+    .expect("region activity panicked");
+    if let Some(callbook) = callbook {
+        // the activity may be shared with the snapshot cache, enrich a copy
+        let mut activity = spot_db::RegionActivity::clone(&region.activity);
+        region.activity = tokio::task::spawn_blocking(move || {
+            let mut entries = callbook.lookup_all(activity.call_info.keys().map(String::as_str));
+            for (call, info) in activity.call_info.iter_mut() {
+                if let Some(entry) = entries.remove(call) {
+                    info.name = entry.name;
+                    info.grid = entry.grid;
+                    info.country = entry.country;
+                }
+            }
+            Arc::new(activity)
+        })
+        .await
+        .expect("callbook lookup panicked");
     }
+    Ok(warp::reply::json(&region))
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct FrequencyInfo {
     pub callsigns: Vec<String>,
+    pub stations: Vec<spot_db::FrequencyUser>,
+}
+
+const DEFAULT_TOLERANCE_HZ: u64 = 200;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct FrequencyQuery {
+    /// correct spot frequencies by the estimated skimmer offset
+    #[serde(default)]
+    pub calibrated: bool,
+    /// +/- window around a single frequency, ignored for ranges
+    pub tolerance_hz: Option<u64>,
+    /// comma separated list of modes, e.g. CW,RTTY
+    pub mode: Option<String>,
+    /// ignore spots older than this
+    pub max_age_secs: Option<u64>,
+}
+
+/// Parses either a single frequency "7016500" or a range "7000000-7040000" (Hz).
+/// `None` if invalid or the tolerance overflows.
+fn parse_frequency_range(freq: &str, tolerance_hz: u64) -> Option<(Frequency, Frequency)> {
+    let (lower_hz, upper_hz) = match freq.split_once('-') {
+        Some((lower, upper)) => (lower.parse::<u64>().ok()?, upper.parse::<u64>().ok()?),
+        None => {
+            let f: u64 = freq.parse().ok()?;
+            (f.saturating_sub(tolerance_hz), f.checked_add(tolerance_hz)?)
+        }
+    };
+    if lower_hz > upper_hz {
+        return None;
+    }
+    Some((
+        Frequency::new::<hertz>(lower_hz as f64),
+        Frequency::new::<hertz>(upper_hz as f64),
+    ))
 }
 
 pub async fn get_frequency(
-    freq_hz: u64,
+    freq: String,
+    query: FrequencyQuery,
     shared_db: SharedDB,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     debug!("--> get_frequency");
-    let db = shared_db.read();
-    let freq = Frequency::new::<hertz>(freq_hz as f64);
-    let callsigns = db.get_frequency_users(freq);
-    Ok(warp::reply::json(&FrequencyInfo { callsigns }))
+    let tolerance_hz = query.tolerance_hz.unwrap_or(DEFAULT_TOLERANCE_HZ);
+    let Some((lower, upper)) = parse_frequency_range(&freq, tolerance_hz) else {
+        return Err(warp::reject::not_found());
+    };
+    let filter = spot_db::FrequencyFilter {
+        modes: query.mode.as_deref().map(parse_modes).unwrap_or_default(),
+        max_age: query.max_age_secs.map(Duration::from_secs),
+        calibrated: query.calibrated,
+    };
+    let stations = shared_db
+        .query(move |db| db.get_frequency_users(lower, upper, &filter))
+        .await;
+    let callsigns = stations.iter().map(|s| s.callsign.clone()).collect();
+    Ok(warp::reply::json(&FrequencyInfo {
+        callsigns,
+        stations,
+    }))
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -52,8 +238,9 @@ pub struct Regions {
 
 pub async fn get_regions(shared_db: SharedDB) -> Result<impl warp::Reply, warp::Rejection> {
     println!("handling regions request");
-    let db = shared_db.read();
-    let mut names: Vec<String> = db.get_regions().iter().map(|r| r.name.clone()).collect();
+    let mut names: Vec<String> = shared_db
+        .query(|db| db.get_regions().iter().map(|r| r.name.clone()).collect())
+        .await;
     names.sort_unstable();
     Ok(warp::reply::json(&Regions { names }))
 }
@@ -61,8 +248,12 @@ pub async fn get_regions(shared_db: SharedDB) -> Result<impl warp::Reply, warp::
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Region {
     pub name: String,
-    pub spotters: Vec<String>,
     pub num_spotter_spots: usize,
-    pub band_activities: Vec<spot_db::BandActivity>,
-    pub call_info: HashMap<String, spot_db::CallInfo>,
+    /// transmissions heard by the region's skimmers, see /signals
+    pub num_signals: usize,
+    #[serde(flatten)]
+    pub activity: Arc<spot_db::RegionActivity>,
+    /// only with ?user=
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_status: Option<spot_db::UserStatus>,
 }
@@ -0,0 +1,40 @@
+use log::debug;
+
+use crate::spot_db;
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SignalsQuery {
+    pub call: Option<String>,
+    pub band: Option<String>,
+    /// only use skimmers of this region
+    pub region: Option<String>,
+}
+
+pub async fn get_signals(
+    query: SignalsQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_signals");
+    let filter = spot_db::SignalFilter {
+        callsign: query.call.map(|c| c.to_uppercase()),
+        band: query.band,
+        region: query.region,
+        ..Default::default()
+    };
+    let signals = shared_db.query(move |db| db.get_signals(&filter)).await;
+    Ok(warp::reply::json(&Signals {
+        num_spots: signals.iter().map(|s| s.num_spots).sum(),
+        num_signals: signals.len(),
+        signals,
+    }))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Signals {
+    pub num_spots: usize,
+    pub num_signals: usize,
+    pub signals: Vec<spot_db::Signal>,
+}
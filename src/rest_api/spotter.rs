@@ -0,0 +1,44 @@
+use log::debug;
+
+use crate::spot_db;
+use crate::spot_db::SharedDB;
+
+use serde::{Deserialize, Serialize};
+
+pub async fn get_spotter(
+    callsign: String,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_spotter");
+    let spotter = shared_db
+        .query(move |db| db.get_spotter(&callsign.to_uppercase()))
+        .await;
+    match spotter {
+        Some(spotter) => Ok(warp::reply::json(&spotter)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SpottersQuery {
+    pub region: Option<String>,
+}
+
+pub async fn get_spotters(
+    query: SpottersQuery,
+    shared_db: SharedDB,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    debug!("--> get_spotters");
+    let spotters: Vec<spot_db::SpotterSummary> = shared_db
+        .query(|db| db.get_spotters())
+        .await
+        .into_iter()
+        .filter(|s| query.region.as_ref().is_none_or(|r| s.regions.contains(r)))
+        .collect();
+    Ok(warp::reply::json(&Spotters { spotters }))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Spotters {
+    pub spotters: Vec<spot_db::SpotterSummary>,
+}
@@ -1,6 +1,7 @@
 use log::debug;
 
-use crate::spot_db::SharedDB;
+use crate::space_weather::SpaceWeather;
+use crate::spot_db::{MemoryUsage, SharedDB};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,13 +9,21 @@ pub async fn get_db_stats(shared_db: SharedDB) -> Result<impl warp::Reply, warp:
     debug!("--> get_db_stats");
     const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
-    let db = shared_db.read();
-    let start_time = format!("{}", db.init_timestamp.format(FORMAT));
-    let stats = DBStats {
-        running_since: start_time,
-        total_spots: db.spots_in_db(),
-        total_regions: db.regions_in_db(),
-    };
+    let stats = shared_db
+        .query(|db| DBStats {
+            running_since: format!("{}", db.init_timestamp.format(FORMAT)),
+            total_spots: db.spots_in_db(),
+            total_signals: db.signals_in_db(),
+            out_of_band_spots: db.out_of_band_spots().len(),
+            out_of_segment_spots: db.out_of_segment_spots().len(),
+            beacon_spots: db.beacon_spots().len(),
+            total_regions: db.regions_in_db(),
+            max_spots: db.max_spots(),
+            evicted_spots: db.evicted_spots(),
+            memory: db.memory_usage(),
+            space_weather: db.space_weather().last().cloned(),
+        })
+        .await;
     Ok(warp::reply::json(&stats))
 }
 
@@ -22,5 +31,17 @@ pub async fn get_db_stats(shared_db: SharedDB) -> Result<impl warp::Reply, warp:
 pub struct DBStats {
     pub running_since: String,
     pub total_spots: usize,
+    /// spots of the same transmission grouped across skimmers
+    pub total_signals: usize,
+    pub out_of_band_spots: usize,
+    /// in the wrong segment for their mode, included in total_spots
+    pub out_of_segment_spots: usize,
+    pub beacon_spots: usize,
     pub total_regions: usize,
+    pub max_spots: Option<usize>,
+    pub evicted_spots: u64,
+    /// estimated bytes per component
+    pub memory: MemoryUsage,
+    /// latest solar and geomagnetic indices
+    pub space_weather: Option<SpaceWeather>,
 }
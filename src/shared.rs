@@ -23,6 +23,33 @@ impl<T> Shared<T> {
     }
 }
 
+impl<T: Send + Sync + 'static> Shared<T> {
+    /// Runs `f` under a read lock on the blocking thread pool, so async tasks
+    /// never wait for the lock on a runtime thread.
+    pub async fn query<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let inner = self.clone();
+        tokio::task::spawn_blocking(move || f(&inner.read()))
+            .await
+            .expect("query panicked")
+    }
+
+    /// Like `query`, but with a write lock.
+    pub async fn update<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let inner = self.clone();
+        tokio::task::spawn_blocking(move || f(&mut inner.write()))
+            .await
+            .expect("update panicked")
+    }
+}
+
 impl<T> Clone for Shared<T> {
     fn clone(&self) -> Self {
         Shared(self.0.clone())
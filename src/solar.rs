@@ -0,0 +1,140 @@
+use crate::geo::LatLon;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The sun is this close to the horizon, above or below, on the grey line.
+const GREYLINE_ELEVATION_DEG: f64 = 6.0;
+
+/// Position of the sun at a station.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SolarState {
+    Day,
+    Night,
+    Greyline,
+}
+
+impl SolarState {
+    pub fn at(location: &LatLon, time: DateTime<Utc>) -> Self {
+        let elevation = sun_elevation(location, time);
+        if elevation.abs() <= GREYLINE_ELEVATION_DEG {
+            Self::Greyline
+        } else if elevation > 0.0 {
+            Self::Day
+        } else {
+            Self::Night
+        }
+    }
+}
+
+/// Where the sun is in the zenith at `time`, accurate to about 0.1°.
+pub fn subsolar_point(time: DateTime<Utc>) -> LatLon {
+    // days since J2000.0
+    let d = (time.timestamp_millis() as f64 / 86_400_000.0) - 10_957.5;
+    let mean_anomaly = (357.529 + 0.985_600_28 * d).to_radians();
+    let mean_longitude = 280.459 + 0.985_647_36 * d;
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin())
+            .to_radians();
+    let obliquity = (23.439 - 0.000_000_36 * d).to_radians();
+    let right_ascension = (obliquity.cos() * ecliptic_longitude.sin())
+        .atan2(ecliptic_longitude.cos())
+        .to_degrees();
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+    let sidereal_deg = 280.460_618_37 + 360.985_647_366_29 * d;
+    LatLon {
+        lat: declination.to_degrees(),
+        lon: (right_ascension - sidereal_deg + 180.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Elevation of the sun above the horizon at `location`, in degrees.
+pub fn sun_elevation(location: &LatLon, time: DateTime<Utc>) -> f64 {
+    let sun = subsolar_point(time);
+    let (lat, decl) = (location.lat.to_radians(), sun.lat.to_radians());
+    let hour_angle = (location.lon - sun.lon).to_radians();
+    (lat.sin() * decl.sin() + lat.cos() * decl.cos() * hour_angle.cos())
+        .clamp(-1.0, 1.0)
+        .asin()
+        .to_degrees()
+}
+
+/// Points of the day/night terminator at `time`, one every `step_deg`
+/// degrees of longitude from -180° to 180°.
+pub fn terminator(time: DateTime<Utc>, step_deg: f64) -> Vec<LatLon> {
+    let sun = subsolar_point(time);
+    // the terminator is undefined right at the equinox, nudge it
+    let tan_decl = match sun.lat.to_radians().tan() {
+        t if t.abs() < 1e-9 => 1e-9,
+        t => t,
+    };
+    let steps = (360.0 / step_deg).round() as usize;
+    (0..=steps)
+        .map(|i| {
+            let lon = -180.0 + i as f64 * step_deg;
+            let hour_angle = (lon - sun.lon).to_radians();
+            LatLon {
+                lat: (-hour_angle.cos() / tan_decl).atan().to_degrees(),
+                lon,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    #[rstest]
+    // June solstice, noon at Greenwich
+    #[case((2024, 6, 20, 12), 23.4, 0.0)]
+    // December solstice, midnight at Greenwich
+    #[case((2024, 12, 21, 0), -23.4, 180.0)]
+    fn subsolar(#[case] time: (i32, u32, u32, u32), #[case] lat: f64, #[case] lon: f64) {
+        let time = Utc
+            .with_ymd_and_hms(time.0, time.1, time.2, time.3, 0, 0)
+            .unwrap();
+        let sun = subsolar_point(time);
+        assert!((sun.lat - lat).abs() < 0.2, "{sun:?}");
+        // within the equation of time, a few degrees at most
+        let dlon = (sun.lon - lon + 180.0).rem_euclid(360.0) - 180.0;
+        assert!(dlon.abs() < 2.0, "{sun:?}");
+    }
+
+    #[rstest]
+    // Berlin, summer
+    #[case((52.5, 13.4), (2024, 6, 20, 11), SolarState::Day)]
+    #[case((52.5, 13.4), (2024, 6, 20, 23), SolarState::Night)]
+    // sunset in Berlin is around 19:30 UTC at the solstice
+    #[case((52.5, 13.4), (2024, 6, 20, 19), SolarState::Greyline)]
+    // Sydney, same time
+    #[case((-33.9, 151.2), (2024, 6, 20, 11), SolarState::Night)]
+    fn solar_state(
+        #[case] location: (f64, f64),
+        #[case] time: (i32, u32, u32, u32),
+        #[case] expected: SolarState,
+    ) {
+        let location = LatLon {
+            lat: location.0,
+            lon: location.1,
+        };
+        let time = Utc
+            .with_ymd_and_hms(time.0, time.1, time.2, time.3, 30, 0)
+            .unwrap();
+        assert_eq!(SolarState::at(&location, time), expected);
+    }
+
+    #[rstest]
+    fn terminator_has_sun_on_horizon() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap();
+        let points = terminator(time, 10.0);
+        assert_eq!(points.len(), 37);
+        assert_eq!(points[0].lon, -180.0);
+        assert_eq!(points[36].lon, 180.0);
+        for point in points {
+            assert!(sun_elevation(&point, time).abs() < 0.01, "{point:?}");
+        }
+    }
+}
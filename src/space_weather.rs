@@ -0,0 +1,181 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Downloads taking longer than this fail.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Solar and geomagnetic indices of one report.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SpaceWeather {
+    pub issued: DateTime<Utc>,
+    /// 10.7 cm solar flux index (SFI)
+    pub solar_flux: Option<u32>,
+    /// estimated planetary A-index
+    pub a_index: Option<u32>,
+    /// estimated planetary K-index
+    pub k_index: Option<f64>,
+}
+
+/// Where space weather reports come from.
+pub trait SpaceWeatherProvider: Send + Sync {
+    /// Latest report, blocks while fetching.
+    fn fetch(&self) -> Result<SpaceWeather>;
+}
+
+/// Reads a report in the NOAA/WWV text format from a local file.
+pub struct FileProvider {
+    path: String,
+}
+
+impl FileProvider {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+impl SpaceWeatherProvider for FileProvider {
+    fn fetch(&self) -> Result<SpaceWeather> {
+        let text = fs::read_to_string(&self.path)
+            .map_err(|e| anyhow!("failed to read {}: {e}", self.path))?;
+        parse_wwv(&text).map_err(|e| anyhow!("failed to read {}: {e}", self.path))
+    }
+}
+
+/// Downloads a report in the NOAA/WWV text format, e.g.
+/// https://services.swpc.noaa.gov/text/wwv.txt
+pub struct HttpProvider {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpProvider {
+    pub fn new(url: String) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(HTTP_TIMEOUT))
+            .build()
+            .into();
+        Self { url, agent }
+    }
+}
+
+impl SpaceWeatherProvider for HttpProvider {
+    fn fetch(&self) -> Result<SpaceWeather> {
+        let text = self
+            .agent
+            .get(&self.url)
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(|e| anyhow!("failed to fetch {}: {e}", self.url))?;
+        parse_wwv(&text).map_err(|e| anyhow!("failed to read {}: {e}", self.url))
+    }
+}
+
+/// A provider for `source`, downloaded if it is an http(s) URL, else read
+/// from the local file.
+pub fn provider(source: &str) -> Arc<dyn SpaceWeatherProvider> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        Arc::new(HttpProvider::new(source.to_string()))
+    } else {
+        Arc::new(FileProvider::new(source.to_string()))
+    }
+}
+
+/// First number following `marker` in `text`.
+fn number_after<T: std::str::FromStr>(text: &str, marker: &str) -> Option<T> {
+    let (_, rest) = text.split_once(marker)?;
+    let number: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
+
+/// Parses a geophysical alert message like
+///
+/// ```text
+/// :Issued: 2024 Mar 01 1805 UTC
+/// Solar flux 156 and estimated planetary A-index 9.
+/// The estimated planetary K-index at 1800 UTC on 01 March was 2.
+/// ```
+pub fn parse_wwv(text: &str) -> Result<SpaceWeather> {
+    let issued = text
+        .lines()
+        .find_map(|l| l.strip_prefix(":Issued:"))
+        .context("no :Issued: line")?;
+    let issued = NaiveDateTime::parse_from_str(issued.trim(), "%Y %b %d %H%M UTC")
+        .map_err(|e| anyhow!("invalid issue time {}: {e}", issued.trim()))?
+        .and_utc();
+    // the indices are spread over wrapped sentences
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let weather = SpaceWeather {
+        issued,
+        solar_flux: number_after(&text, "Solar flux"),
+        a_index: number_after(&text, "planetary A-index"),
+        k_index: text
+            .split_once("planetary K-index")
+            .and_then(|(_, rest)| number_after(rest, " was")),
+    };
+    if weather.solar_flux.is_none() && weather.a_index.is_none() && weather.k_index.is_none() {
+        return Err(anyhow!("no solar flux, A- or K-index"));
+    }
+    Ok(weather)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    const WWV: &str = "\
+:Product: Geophysical Alert Message wwv.txt
+:Issued: 2024 Mar 01 1805 UTC
+# Prepared by the US Dept. of Commerce, NOAA, Space Weather Prediction Center
+#
+#          Geophysical Alert Message
+#
+Solar-terrestrial indices for 29 February follow.
+Solar flux 156 and estimated planetary A-index 9.
+The estimated planetary K-index at 1800 UTC on 01
+March was 2.33.
+
+No space weather storms were observed for the past 24 hours.
+";
+
+    #[rstest]
+    fn parse_report() {
+        let weather = parse_wwv(WWV).unwrap();
+        assert_eq!(
+            weather,
+            SpaceWeather {
+                issued: Utc.with_ymd_and_hms(2024, 3, 1, 18, 5, 0).unwrap(),
+                solar_flux: Some(156),
+                a_index: Some(9),
+                k_index: Some(2.33),
+            }
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case(":Issued: yesterday\nSolar flux 156")]
+    #[case(":Issued: 2024 Mar 01 1805 UTC\nNothing to report.")]
+    fn invalid_report(#[case] text: &str) {
+        assert!(parse_wwv(text).is_err());
+    }
+
+    #[rstest]
+    fn file_provider() {
+        let path = std::env::temp_dir().join(format!("wwv-{}.txt", std::process::id()));
+        fs::write(&path, WWV).unwrap();
+        let provider = provider(path.to_str().unwrap());
+        assert_eq!(provider.fetch().unwrap().solar_flux, Some(156));
+        fs::remove_file(&path).unwrap();
+        assert!(provider.fetch().is_err());
+    }
+}
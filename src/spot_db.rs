@@ -1,15 +1,62 @@
-use crate::bands::HF_BANDS;
+use crate::activations::{Activations, Program, ProgramReference};
+use crate::bands::{BandPlan, SegmentKind};
+use crate::beacons::is_ncdxf_beacon;
+use crate::geo::{Locations, Path};
 use crate::shared;
-use chrono::{DateTime, Utc};
+use crate::solar::SolarState;
+use crate::space_weather::SpaceWeather;
+use chrono::{DateTime, TimeDelta, Utc};
 use core::ops::Sub;
-use log::{debug, error};
+use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use uom::si::f64::Frequency;
 use uom::si::frequency::kilohertz;
 
+mod aggregate;
+mod alerts;
+mod bandmap;
+mod beacons;
+mod busted;
+mod calibration;
+mod contest;
+mod frequency;
+mod greyline;
+mod location;
+mod memory;
+mod propagation;
+mod signals;
+mod space_weather;
+mod spotters;
+mod store;
+mod worked;
+
+use self::aggregate::RegionAggregate;
+use self::alerts::AlertKey;
+pub use self::alerts::{Alert, AlertPreferences};
+pub use self::beacons::{BeaconReport, Beacons, NcdxfBand, NcdxfBeaconReport};
+pub use self::calibration::Calibration;
+use self::contest::BandCalls;
+pub use self::contest::{
+    Contest, ContestBand, ContestProfile, ContestSnapshot, Multiplier, MultiplierKind, wpx_prefix,
+};
+pub use self::frequency::{FrequencyFilter, FrequencyUser};
+pub use self::greyline::{Greyline, GreylinePath};
+pub use self::location::{Locator, RegionKind, StationLocation};
+pub use self::memory::MemoryUsage;
+pub use self::propagation::{
+    AreaPropagation, BandPropagation, Propagation, PropagationSnapshot, SnrSummary, Trend,
+};
+use self::signals::SignalIndex;
+pub use self::signals::{Signal, SignalFilter, SignalId, SignalSkimmer};
+pub use self::spotters::SpotterSummary;
+pub use self::store::SpotId;
+use self::store::SpotStore;
+use self::worked::WorkedLog;
+pub use self::worked::{BandStatus, CallStatus, UserStatus, WorkedStatus};
+
 pub type SharedDB = shared::Shared<SpotDB>;
 
 #[derive(Debug, PartialEq)]
@@ -17,25 +64,160 @@ pub struct Spot {
     pub spotter: String, // e.g. "G4IRN"
     pub spotted: String, // spotted callsign
     pub freq: Frequency,
-    mode: String,    // CW, SSB, FT8 …
-    pub snr_db: i32, // signal‑to‑noise ratio, dB
-    pub wpm: u32,    // words‑per‑minute
-    msg: String,     // usually "CQ"
+    pub mode: String, // CW, SSB, FT8 …
+    pub snr_db: i32,  // signal‑to‑noise ratio, dB
+    pub wpm: u32,     // words‑per‑minute
+    pub msg: String,  // usually "CQ"
     pub timestamp: DateTime<Utc>,
+    pub band: Option<String>, // None if outside of the band plan
+    pub segment: Option<SegmentKind>,
+    /// in no segment of its band, or in one for another mode
+    pub out_of_segment: bool,
+    /// spotter to spotted, `None` if either location is unknown
+    pub path: Option<Path>,
+    /// sun at both ends when spotted, `None` if the location is unknown
+    pub spotter_solar: Option<SolarState>,
+    pub spotted_solar: Option<SolarState>,
+    /// activations the spotted station was scheduled for when spotted
+    pub references: Vec<ProgramReference>,
+}
+
+/// Type of a spot, from its RBN message.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MsgType {
+    Cq,
+    Dx,
+    Ncdxf,
+    Beacon,
+    Other,
+}
+
+impl MsgType {
+    pub fn from_msg(msg: &str) -> Self {
+        match msg.to_uppercase().as_str() {
+            "CQ" => Self::Cq,
+            "DX" => Self::Dx,
+            "NCDXF" => Self::Ncdxf,
+            "BEACON" => Self::Beacon,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Spot {
+    pub fn msg_type(&self) -> MsgType {
+        MsgType::from_msg(&self.msg)
+    }
+
+    /// Whether the station was activating a reference of one of `programs`,
+    /// always true if there are none.
+    pub fn activates(&self, programs: &[Program]) -> bool {
+        programs.is_empty()
+            || self
+                .references
+                .iter()
+                .any(|r| programs.contains(&r.program))
+    }
+}
+
+/// How long a computed region activity is reused for identical requests.
+const SNAPSHOT_TTL: Duration = Duration::from_secs(2);
+
+// query -> (computed at, activity)
+type Snapshots = HashMap<ActivityQuery, (Instant, Arc<RegionActivity>)>;
+
+/// Selects what a region activity contains, also the key of its snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ActivityQuery {
+    /// activity time windows, sorted ascending
+    pub windows: Vec<Duration>,
+    /// also list calls flagged as probably busted
+    pub include_busted: bool,
+    /// only spots in these modes, all modes if empty
+    pub modes: Vec<String>,
+    /// only spots over at least / at most this distance, spots of unknown
+    /// distance are left out if either is set
+    pub min_distance_km: Option<u32>,
+    pub max_distance_km: Option<u32>,
+    /// only calls activating a reference of these programs, all calls if
+    /// empty
+    pub programs: Vec<Program>,
+}
+
+impl ActivityQuery {
+    fn matches_distance(&self, distance_km: Option<u32>) -> bool {
+        if self.min_distance_km.is_none() && self.max_distance_km.is_none() {
+            return true;
+        }
+        distance_km.is_some_and(|d| {
+            self.min_distance_km.is_none_or(|min| d >= min)
+                && self.max_distance_km.is_none_or(|max| d <= max)
+        })
+    }
 }
 
+/// A region is its own shard: its spots are behind a separate lock, so
+/// computing the activity of one region neither holds the database lock nor
+/// blocks ingestion of spots of other regions.
 pub struct Region {
     pub name: String,
-    pub spotter_spots: Vec<Arc<Spot>>,
     pub prefixes: Vec<String>,
+    spots: RwLock<RegionSpots>,
+    snapshots: Mutex<Snapshots>,
+}
+
+// spots of skimmers in this region
+#[derive(Default)]
+struct RegionSpots {
+    ids: HashMap<SpotId, SignalId>,
+    // number of region spots per signal
+    signals: HashMap<SignalId, usize>,
+    aggregate: RegionAggregate,
+    // beacon spots, kept apart from the activity like in the database
+    beacons: RegionAggregate,
+}
+
+/// Calls whose newest spot is within `window_mins` but not within any
+/// shorter window.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ActivityBucket {
+    pub window_mins: u64,
+    pub calls: Vec<String>,
+}
+
+/// Activity of a single mode on a band.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ModeActivity {
+    pub mode: String,
+    pub buckets: Vec<ActivityBucket>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct BandActivity {
     pub band: String,
-    pub active_1min: Vec<String>,
-    pub active_5min: Vec<String>,
-    pub active_15min: Vec<String>,
+    /// all selected modes together
+    pub buckets: Vec<ActivityBucket>,
+    /// broken down per mode, modes without calls are left out
+    pub modes: Vec<ModeActivity>,
+    /// longest skimmer to station path of the selected spots
+    pub longest_path: Option<LongestPath>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LongestPath {
+    pub spotter: String,
+    pub spotted: String,
+    pub distance_km: u32,
+    pub bearing_deg: u16,
+}
+
+/// Band activities, skimmers and call details of a region.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RegionActivity {
+    pub band_activities: Vec<BandActivity>,
+    pub spotters: Vec<String>,
+    pub call_info: HashMap<String, CallInfo>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -43,136 +225,372 @@ pub struct CallInfo {
     pub frequencies: Vec<f64>,
     pub wpm: Vec<u32>,
     pub db: Vec<i32>,
+    /// modes and message types of the call's spots selected by the query
+    pub modes: Vec<String>,
+    pub msg_types: Vec<MsgType>,
+    /// heard by a single skimmer only, next to a similar confirmed call
+    pub busted: bool,
+    /// references the call was activating in its spots
+    pub references: Vec<ProgramReference>,
+    /// from the callbook, only with ?enrich=true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+/// How a single skimmer hears a station on one band.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SkimmerReport {
+    pub spotter: String,
+    pub regions: Vec<String>,
+    pub num_spots: usize,
+    pub frequency: f64, // kHz, most recent spot
+    pub snr_db: i32,    // most recent spot
+    pub max_snr_db: i32,
+    pub wpm: u32, // most recent spot
+    pub distance_km: Option<u32>,
+    pub bearing_deg: Option<u16>,
+    // most recent spot
+    pub spotter_solar: Option<SolarState>,
+    pub spotted_solar: Option<SolarState>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// All current spots of a station on one band, grouped by skimmer.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CallBandActivity {
+    pub band: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub frequencies: Vec<f64>,
+    pub wpm: Vec<u32>,
+    /// references the station was activating in its spots
+    pub references: Vec<ProgramReference>,
+    pub skimmers: Vec<SkimmerReport>,
 }
 
 impl Region {
     pub fn new(name: String, prefixes: Vec<String>) -> Self {
-        let spots = Vec::new();
         Self {
             name,
-            spotter_spots: spots,
             prefixes,
+            spots: RwLock::new(RegionSpots::default()),
+            snapshots: Mutex::new(HashMap::new()),
         }
     }
     pub fn match_callsign(&self, callsign: &str) -> bool {
         self.prefixes.iter().any(|p| callsign.starts_with(p))
     }
-    pub fn add_spot(&mut self, spot: Arc<Spot>) {
+    pub fn add_spot(&self, id: SpotId, signal: SignalId, spot: &Spot) {
         if self.match_callsign(&spot.spotter) {
-            self.spotter_spots.push(spot);
-        }
-    }
-    pub fn remove_spots(&mut self, spots: &[Arc<Spot>]) {
-        for remove_spot in spots {
-            self.spotter_spots.retain(|s| *s != *remove_spot);
+            let mut spots = self.spots.write().expect("RwLock poisoned");
+            spots.aggregate.add(spot);
+            spots.ids.insert(id, signal);
+            *spots.signals.entry(signal).or_insert(0) += 1;
         }
     }
-    pub fn get_band_activities(
-        &self,
-    ) -> (Vec<BandActivity>, Vec<String>, HashMap<String, CallInfo>) {
-        debug!("--> get_band_activity");
-        let mut band_activity = HashMap::new();
-        let mut spotters = Vec::new();
-        let mut call_info = HashMap::new();
-        for band in HF_BANDS {
-            band_activity.insert(
-                band.name.to_string(),
-                BandActivity {
-                    band: band.name.to_string(),
-                    ..Default::default()
-                },
-            );
+    pub fn remove_spots(&self, expired: &[(SpotId, Arc<Spot>)]) {
+        let mut expired = expired
+            .iter()
+            .filter(|(_, s)| self.match_callsign(&s.spotter))
+            .peekable();
+        if expired.peek().is_none() {
+            return;
         }
-        for spot in self.spotter_spots.clone() {
-            spotters.push(spot.spotter.clone());
-            for band in HF_BANDS {
-                if band.lower <= spot.freq && spot.freq <= band.upper {
-                    let ba = band_activity
-                        .get_mut(band.name)
-                        .expect("initialized hashmap is missing entry!");
-                    if Utc::now() - Duration::from_secs(60) < spot.timestamp {
-                        ba.active_1min.push(spot.spotted.clone());
-                        upser_call_info(&spot, &mut call_info);
-                        continue; // only list the newest spot, ignore 5 and 15min
-                    }
-                    if Utc::now() - Duration::from_secs(5 * 60) < spot.timestamp {
-                        ba.active_5min.push(spot.spotted.clone());
-                        upser_call_info(&spot, &mut call_info);
-                        continue; // ignore 15min
-                    }
-                    if Utc::now() - Duration::from_secs(15 * 60) < spot.timestamp {
-                        ba.active_15min.push(spot.spotted.clone());
-                        upser_call_info(&spot, &mut call_info);
-                    }
+        let mut spots = self.spots.write().expect("RwLock poisoned");
+        for (id, spot) in expired {
+            let Some(signal) = spots.ids.remove(id) else {
+                continue;
+            };
+            spots.aggregate.remove(spot);
+            if let Some(count) = spots.signals.get_mut(&signal) {
+                *count -= 1;
+                if *count == 0 {
+                    spots.signals.remove(&signal);
                 }
             }
         }
-        spotters.sort_unstable();
-        spotters.dedup();
-        // convert to vector in order of HF_BANDS
-        // remove all duplicate callsigns
-        (
-            HF_BANDS
-                .iter()
-                .map(|b| {
-                    let mut ba = band_activity.get(b.name).unwrap().clone();
-                    ba.active_1min.sort_unstable();
-                    ba.active_1min.dedup();
-                    ba.active_5min.sort_unstable();
-                    ba.active_5min.dedup();
-                    ba.active_15min.sort_unstable();
-                    ba.active_15min.dedup();
-                    ba
-                })
-                .collect(),
+    }
+    fn add_beacon(&self, spot: &Spot) {
+        if self.match_callsign(&spot.spotter) {
+            let mut spots = self.spots.write().expect("RwLock poisoned");
+            spots.beacons.add(spot);
+        }
+    }
+    fn remove_beacons(&self, expired: &[Arc<Spot>]) {
+        let mut expired = expired
+            .iter()
+            .filter(|s| self.match_callsign(&s.spotter))
+            .peekable();
+        if expired.peek().is_none() {
+            return;
+        }
+        let mut spots = self.spots.write().expect("RwLock poisoned");
+        for spot in expired {
+            spots.beacons.remove(spot);
+        }
+    }
+    pub fn num_spots(&self) -> usize {
+        self.spots.read().expect("RwLock poisoned").ids.len()
+    }
+    /// Number of signals heard by skimmers of this region.
+    pub fn num_signals(&self) -> usize {
+        self.spots.read().expect("RwLock poisoned").signals.len()
+    }
+    fn memory_bytes(&self) -> usize {
+        let spots = self.spots.read().expect("RwLock poisoned");
+        (spots.ids.capacity() + spots.signals.capacity()) * size_of::<(u64, usize)>()
+            + spots.aggregate.memory_bytes()
+            + spots.beacons.memory_bytes()
+    }
+    fn spot_ids(&self) -> Vec<SpotId> {
+        self.spots
+            .read()
+            .expect("RwLock poisoned")
+            .ids
+            .keys()
+            .copied()
+            .collect()
+    }
+    /// Like `get_band_activities`, but always computed from the aggregate.
+    pub fn compute_band_activities(
+        &self,
+        band_plan: &BandPlan,
+        query: &ActivityQuery,
+        busted: &HashSet<String>,
+    ) -> RegionActivity {
+        let (band_activities, spotters, call_info) = self
+            .spots
+            .read()
+            .expect("RwLock poisoned")
+            .aggregate
+            .band_activities(band_plan, query, busted, Utc::now());
+        RegionActivity {
+            band_activities,
             spotters,
             call_info,
-        )
+        }
+    }
+
+    /// Sorts the spots of this region into bands and into the time windows
+    /// of `query`. A call is listed in the shortest window containing its
+    /// newest spot. `busted` calls are flagged, and left out unless the
+    /// query includes them. The result is reused for `SNAPSHOT_TTL`.
+    pub fn get_band_activities(
+        &self,
+        band_plan: &BandPlan,
+        query: &ActivityQuery,
+        busted: &HashSet<String>,
+    ) -> Arc<RegionActivity> {
+        debug!("--> get_band_activity");
+        if let Some((computed, activity)) =
+            self.snapshots.lock().expect("Mutex poisoned").get(query)
+            && computed.elapsed() < SNAPSHOT_TTL
+        {
+            return activity.clone();
+        }
+        // computed without the snapshot lock, so a failing computation can't
+        // poison it for later requests
+        let activity = Arc::new(self.compute_band_activities(band_plan, query, busted));
+        let mut snapshots = self.snapshots.lock().expect("Mutex poisoned");
+        snapshots.retain(|_, (computed, _)| computed.elapsed() < SNAPSHOT_TTL);
+        snapshots.insert(query.clone(), (Instant::now(), activity.clone()));
+        activity
     }
 }
 
-fn upser_call_info(spot: &Spot, call_infos: &mut HashMap<String, CallInfo>) {
-    match call_infos.entry(spot.spotted.clone()) {
-        std::collections::hash_map::Entry::Occupied(mut occ) => {
-            let orig = occ.get_mut();
-            orig.frequencies.push(spot.freq.get::<kilohertz>());
-            orig.frequencies.sort_by(f64::total_cmp);
-            orig.frequencies.dedup();
-            orig.wpm.push(spot.wpm);
-            orig.wpm.sort_unstable();
-            orig.wpm.dedup();
-            orig.db.push(spot.snr_db);
-            orig.db.sort_unstable();
-            orig.db.dedup();
+/// Spot retention unless set from the config.
+const DEFAULT_MAX_SPOT_AGE: Duration = Duration::from_secs(3600);
+
+/// Share of the spot cap evicted at once when it is exceeded, so not every
+/// new spot pays for an eviction.
+const EVICTION_BATCH_DIVISOR: usize = 100;
+/// While spots are evicted because of the spot cap, warn at most this often.
+const CAPACITY_WARNING_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Start of the `window` ending at `now`, the earliest representable time
+/// if the window reaches back further.
+pub(crate) fn window_start(now: DateTime<Utc>, window: Duration) -> DateTime<Utc> {
+    TimeDelta::from_std(window)
+        .ok()
+        .and_then(|w| now.checked_sub_signed(w))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Median of `values`, sorts them in place. `None` if empty.
+pub(crate) fn median<T: Copy + PartialOrd>(values: &mut [T]) -> Option<T> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(values[values.len() / 2])
+}
+
+impl SkimmerReport {
+    fn new(spot: &Spot, regions: Vec<String>) -> Self {
+        Self {
+            spotter: spot.spotter.clone(),
+            regions,
+            num_spots: 1,
+            frequency: spot.freq.get::<kilohertz>(),
+            snr_db: spot.snr_db,
+            max_snr_db: spot.snr_db,
+            wpm: spot.wpm,
+            distance_km: spot.path.map(|p| p.distance_km),
+            bearing_deg: spot.path.map(|p| p.bearing_deg),
+            spotter_solar: spot.spotter_solar,
+            spotted_solar: spot.spotted_solar,
+            first_seen: spot.timestamp,
+            last_seen: spot.timestamp,
         }
-        std::collections::hash_map::Entry::Vacant(vac) => {
-            let info = CallInfo {
-                frequencies: vec![spot.freq.get::<kilohertz>()],
-                wpm: vec![spot.wpm],
-                db: vec![spot.snr_db],
-            };
-            vac.insert(info);
+    }
+
+    fn update(&mut self, spot: &Spot) {
+        self.num_spots += 1;
+        self.max_snr_db = self.max_snr_db.max(spot.snr_db);
+        self.first_seen = self.first_seen.min(spot.timestamp);
+        if spot.timestamp >= self.last_seen {
+            self.last_seen = spot.timestamp;
+            self.frequency = spot.freq.get::<kilohertz>();
+            self.snr_db = spot.snr_db;
+            self.wpm = spot.wpm;
+            self.spotter_solar = spot.spotter_solar;
+            self.spotted_solar = spot.spotted_solar;
         }
     }
 }
 
 pub struct SpotDB {
     pub init_timestamp: DateTime<Utc>,
-    spots: Vec<Arc<Spot>>,
-    regions: HashMap<String, Region>,
+    band_plan: Arc<BandPlan>,
+    spots: SpotStore,
+    signals: SignalIndex,
+    out_of_band: VecDeque<Arc<Spot>>,
+    // spots in the wrong segment for their mode, still counted as activity
+    out_of_segment: BTreeSet<SpotId>,
+    // beacon spots are kept apart from normal activity
+    beacons: VecDeque<Arc<Spot>>,
+    // calls of beacons that are not NCDXF beacons and don't sign /B
+    beacon_calls: HashSet<String>,
+    regions: HashMap<String, Arc<Region>>,
+    max_spots: Option<usize>,
+    evicted_spots: u64,
+    // last warning about evicting spots because of the spot cap
+    capacity_warned: Option<Instant>,
+    // probably busted calls, and the calls heard by enough skimmers to
+    // bust others
+    busted: Arc<HashSet<String>>,
+    confirmed: HashSet<String>,
+    // spot times per band and call, for contest views
+    band_calls: Arc<RwLock<BandCalls>>,
+    // last spot time of every skimmer seen, survives spot cleanup until
+    // `FORGET_AFTER`
+    spotter_last_seen: HashMap<String, DateTime<Utc>>,
+    calibration: HashMap<String, Calibration>,
+    locations: Arc<Locations>,
+    activations: Activations,
+    // solar and geomagnetic indices, oldest first
+    space_weather: Vec<SpaceWeather>,
+    // user -> entities worked
+    logs: HashMap<String, Arc<WorkedLog>>,
+    alert_preferences: HashMap<String, AlertPreferences>,
+    alerts: VecDeque<Alert>,
+    last_alert_id: u64,
+    // slots already checked for alerts -> time of their newest spot
+    alert_checked: HashMap<AlertKey, DateTime<Utc>>,
+    // spots older than this are cleaned up, the longest query window
+    max_spot_age: Duration,
+}
+
+impl Default for SpotDB {
+    /// Creates a database using the built-in band plan.
+    fn default() -> Self {
+        Self::with_band_plan(BandPlan::default())
+    }
 }
 
 impl SpotDB {
-    pub fn new() -> Self {
-        let spots = Vec::new();
+    pub fn with_band_plan(band_plan: BandPlan) -> Self {
+        let spots = SpotStore::default();
         let regions = HashMap::new();
         Self {
             init_timestamp: Utc::now(),
+            band_plan: Arc::new(band_plan),
             spots,
+            signals: SignalIndex::default(),
+            out_of_band: VecDeque::new(),
+            out_of_segment: BTreeSet::new(),
+            beacons: VecDeque::new(),
+            beacon_calls: HashSet::new(),
             regions,
+            max_spots: None,
+            evicted_spots: 0,
+            capacity_warned: None,
+            busted: Arc::default(),
+            confirmed: HashSet::new(),
+            band_calls: Arc::default(),
+            spotter_last_seen: HashMap::new(),
+            calibration: HashMap::new(),
+            locations: Arc::default(),
+            activations: Activations::default(),
+            space_weather: Vec::new(),
+            logs: HashMap::new(),
+            alert_preferences: HashMap::new(),
+            alerts: VecDeque::new(),
+            last_alert_id: 0,
+            alert_checked: HashMap::new(),
+            max_spot_age: DEFAULT_MAX_SPOT_AGE,
         }
     }
 
+    /// Additional beacon calls, on top of the NCDXF beacons, calls ending in
+    /// /B and spots with a beacon message.
+    pub fn set_beacon_calls(&mut self, calls: impl IntoIterator<Item = String>) {
+        self.beacon_calls = calls.into_iter().collect();
+    }
+
+    fn is_beacon(&self, spot: &Spot) -> bool {
+        matches!(spot.msg_type(), MsgType::Ncdxf | MsgType::Beacon)
+            || spot.spotted.ends_with("/B")
+            || is_ncdxf_beacon(&spot.spotted)
+            || self.beacon_calls.contains(&spot.spotted)
+    }
+
+    /// Skimmer and entity locations, spots added later get their path.
+    pub fn set_locations(&mut self, locations: Locations) {
+        self.locations = Arc::new(locations);
+    }
+
+    /// Award program references and schedules, spots added later get the
+    /// references their station is activating.
+    pub fn set_activations(&mut self, activations: Activations) {
+        self.activations = activations;
+    }
+
+    /// References `callsign` is scheduled to activate at `time`.
+    pub fn get_activations(&self, callsign: &str, time: DateTime<Utc>) -> Vec<ProgramReference> {
+        self.activations.active(callsign, time)
+    }
+
+    /// How long spots are kept, query windows can't be longer.
+    pub fn set_max_spot_age(&mut self, max_spot_age: Duration) {
+        self.max_spot_age = max_spot_age;
+    }
+
+    pub fn max_spot_age(&self) -> Duration {
+        self.max_spot_age
+    }
+
+    /// Limits the number of spots kept, the oldest are evicted beyond it.
+    pub fn set_max_spots(&mut self, max_spots: Option<usize>) {
+        self.max_spots = max_spots;
+        self.enforce_max_spots();
+    }
+
     // refactor another time
     #[allow(clippy::too_many_arguments)]
     pub fn add_spot(
@@ -186,6 +604,9 @@ impl SpotDB {
         msg: &str,
         timestamp: DateTime<Utc>,
     ) {
+        let band = self.band_plan.find_band(freq);
+        let spotter_location = self.locations.skimmer(spotter);
+        let spotted_location = self.locations.station(spotted);
         let spot = Spot {
             spotter: spotter.to_string(),
             spotted: spotted.to_string(),
@@ -195,65 +616,297 @@ impl SpotDB {
             wpm,
             msg: msg.to_string(),
             timestamp,
+            band: band.map(|b| b.name.clone()),
+            segment: band.and_then(|b| b.find_segment(freq)).map(|s| s.kind),
+            out_of_segment: band.is_some_and(|b| b.out_of_segment(freq, mode)),
+            path: spotter_location
+                .zip(spotted_location)
+                .map(|(from, to)| from.path_to(&to)),
+            spotter_solar: spotter_location.map(|l| SolarState::at(&l, timestamp)),
+            spotted_solar: spotted_location.map(|l| SolarState::at(&l, timestamp)),
+            references: self.activations.active(spotted, timestamp),
         };
+        self.spotter_last_seen
+            .entry(spot.spotter.clone())
+            .and_modify(|t| *t = (*t).max(timestamp))
+            .or_insert(timestamp);
         let s = Arc::new(spot);
-        self.spots.push(s.clone());
+        if s.band.is_none() {
+            // busted decodes or bad skimmers, keep them out of the statistics
+            self.out_of_band.push_back(s);
+            self.enforce_max_spots();
+            return;
+        }
+        if self.is_beacon(&s) {
+            self.regions.values().for_each(|r| r.add_beacon(&s));
+            self.beacons.push_back(s);
+            self.enforce_max_spots();
+            return;
+        }
+        let id = self.spots.insert(s.clone());
+        let signal = self.signals.add(id, &s);
+        if s.out_of_segment {
+            self.out_of_segment.insert(id);
+        }
         self.regions
-            .iter_mut()
-            .for_each(|(_, r)| r.add_spot(s.clone()));
+            .values()
+            .for_each(|r| r.add_spot(id, signal, &s));
+        self.band_calls.write().expect("RwLock poisoned").add(&s);
+        self.update_busted(&[&s]);
+        self.check_alerts(&s);
+        self.enforce_max_spots();
+    }
+
+    /// Spots of all stores: normal, out-of-band and beacon spots.
+    fn num_stored_spots(&self) -> usize {
+        self.spots.len() + self.out_of_band.len() + self.beacons.len()
+    }
+
+    /// Evicts the oldest spots of all stores while they hold more than
+    /// `max_spots` together.
+    fn enforce_max_spots(&mut self) {
+        let Some(max) = self.max_spots else {
+            return;
+        };
+        let total = self.num_stored_spots();
+        if total <= max {
+            return;
+        }
+        if self
+            .capacity_warned
+            .is_none_or(|t| t.elapsed() >= CAPACITY_WARNING_INTERVAL)
+        {
+            warn!("spot cap of {max} reached, evicting the oldest spots");
+            self.capacity_warned = Some(Instant::now());
+        }
+        let n = total - max + max / EVICTION_BATCH_DIVISOR;
+        let mut evicted = Vec::new();
+        let mut evicted_beacons = Vec::new();
+        for _ in 0..n {
+            let oldest = [
+                self.spots.oldest_time(),
+                self.out_of_band.front().map(|s| s.timestamp),
+                self.beacons.front().map(|s| s.timestamp),
+            ];
+            let Some((store, _)) = oldest
+                .iter()
+                .enumerate()
+                .filter_map(|(i, t)| Some((i, (*t)?)))
+                .min_by_key(|(_, t)| *t)
+            else {
+                break;
+            };
+            match store {
+                0 => evicted.extend(self.spots.remove_oldest(1)),
+                1 => drop(self.out_of_band.pop_front()),
+                _ => evicted_beacons.extend(self.beacons.pop_front()),
+            }
+            self.evicted_spots += 1;
+        }
+        // slots can be alerted again once their spots are gone
+        if let Some(oldest) = self.spots.oldest_time() {
+            self.alert_checked.retain(|_, t| *t >= oldest);
+        } else {
+            self.alert_checked.clear();
+        }
+        self.unlink_spots(&evicted);
+        self.unlink_beacons(&evicted_beacons);
     }
 
     pub fn cleanup_old_spots(&mut self, max_spot_age: Duration) {
-        let cutoff = Utc::now().sub(max_spot_age);
-        let (expired, active) = self
-            .spots
-            .iter()
-            .cloned()
-            .partition(|s| s.timestamp < cutoff);
-        self.spots = active;
-        self.regions
-            .iter_mut()
-            .for_each(|(_, r)| r.remove_spots(&expired));
+        self.remove_spots_before(Utc::now().sub(max_spot_age));
+    }
+
+    /// Removes all spots older than `cutoff`.
+    pub fn remove_spots_before(&mut self, cutoff: DateTime<Utc>) {
+        let expired = self.spots.remove_before(cutoff);
+        self.out_of_band.retain(|s| s.timestamp >= cutoff);
+        let (beacons, expired_beacons): (Vec<_>, Vec<_>) =
+            self.beacons.drain(..).partition(|s| s.timestamp >= cutoff);
+        self.beacons = beacons.into();
+        // slots can be alerted again once their spots are gone
+        self.alert_checked.retain(|_, t| *t >= cutoff);
+        let forget_before = window_start(cutoff, spotters::FORGET_AFTER);
+        self.spotter_last_seen.retain(|_, t| *t >= forget_before);
+        self.unlink_spots(&expired);
+        self.unlink_beacons(&expired_beacons);
         // sanity check
-        for e in expired {
+        for (_, e) in expired {
             if Arc::strong_count(&e) > 1 {
                 error!("Bug in cleanup somewhere! Arc::strong_count > 1 after delete!");
             }
         }
     }
 
+    /// Drops spots removed from the store from signals, regions, the band
+    /// calls and the busted calls.
+    fn unlink_spots(&mut self, removed: &[(SpotId, Arc<Spot>)]) {
+        for (id, _) in removed {
+            self.signals.remove(*id);
+            self.out_of_segment.remove(id);
+        }
+        self.regions.values().for_each(|r| r.remove_spots(removed));
+        let mut band_calls = self.band_calls.write().expect("RwLock poisoned");
+        for (_, spot) in removed {
+            band_calls.remove(spot);
+        }
+        drop(band_calls);
+        let removed: Vec<&Spot> = removed.iter().map(|(_, s)| s.as_ref()).collect();
+        self.update_busted(&removed);
+    }
+
+    /// Drops removed beacon spots from the regions.
+    fn unlink_beacons(&self, removed: &[Arc<Spot>]) {
+        self.regions
+            .values()
+            .for_each(|r| r.remove_beacons(removed));
+    }
+
     pub fn spots_in_db(&self) -> usize {
         self.spots.len()
     }
 
+    /// All spots of skimmers in `region`.
+    pub fn region_spots(&self, region: &Region) -> Vec<&Arc<Spot>> {
+        region
+            .spot_ids()
+            .into_iter()
+            .filter_map(|id| self.spots.get(id))
+            .collect()
+    }
+
+    pub fn max_spots(&self) -> Option<usize> {
+        self.max_spots
+    }
+
+    /// Number of spots dropped because of the spot cap since startup.
+    pub fn evicted_spots(&self) -> u64 {
+        self.evicted_spots
+    }
+
+    pub fn beacon_spots(&self) -> &VecDeque<Arc<Spot>> {
+        &self.beacons
+    }
+
+    /// Spots in no segment of their band, or in one for another mode.
+    pub fn out_of_segment_spots(&self) -> Vec<&Arc<Spot>> {
+        self.out_of_segment
+            .iter()
+            .filter_map(|id| self.spots.get(*id))
+            .collect()
+    }
+
+    pub fn out_of_band_spots(&self) -> &VecDeque<Arc<Spot>> {
+        &self.out_of_band
+    }
+
+    /// Shared, so it can be used after the database lock is released.
+    pub fn band_plan(&self) -> &Arc<BandPlan> {
+        &self.band_plan
+    }
+
     pub fn regions_in_db(&self) -> usize {
         self.regions.len()
     }
 
     pub fn add_region(&mut self, name: String, prefixes: Vec<String>) {
         let r = Region::new(name.clone(), prefixes);
-        self.regions.insert(name, r);
+        self.regions.insert(name, Arc::new(r));
     }
 
-    pub fn get_region(&self, name: &str) -> Option<&Region> {
+    pub fn get_region(&self, name: &str) -> Option<&Arc<Region>> {
         self.regions.get(name)
     }
 
-    pub fn get_regions(&self) -> Vec<&Region> {
+    pub fn get_regions(&self) -> Vec<&Arc<Region>> {
         self.regions.values().collect()
     }
 
-    pub fn get_frequency_users(&self, freq: Frequency) -> Vec<String> {
-        let delta_f = Frequency::new::<kilohertz>(0.2);
-        let mut callsigns: Vec<String> = self
-            .spots
-            .iter()
-            .filter(|&s| (freq + delta_f) >= s.freq && (freq - delta_f) <= s.freq)
-            .map(|s| s.spotted.clone())
+    /// Names of all regions whose prefixes match `callsign`, sorted.
+    pub fn regions_of(&self, callsign: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .regions
+            .values()
+            .filter(|r| r.match_callsign(callsign))
+            .map(|r| r.name.clone())
             .collect();
-        callsigns.sort_unstable();
-        callsigns.dedup();
-        callsigns
+        names.sort_unstable();
+        names
+    }
+
+    /// Collects all current spots of `callsign`, grouped by band and skimmer.
+    /// Bands are returned in order of the band plan, skimmers by best SNR first.
+    /// With `programs`, only spots activating a reference of them count.
+    pub fn get_call_activity(&self, callsign: &str, programs: &[Program]) -> Vec<CallBandActivity> {
+        debug!("--> get_call_activity");
+        let mut by_band: HashMap<&str, Vec<&Arc<Spot>>> = HashMap::new();
+        for spot in self.spots.by_call(callsign) {
+            if !spot.activates(programs) {
+                continue;
+            }
+            if let Some(band) = &spot.band {
+                by_band.entry(band).or_default().push(spot);
+            }
+        }
+        let mut regions_cache: HashMap<&str, Vec<String>> = HashMap::new();
+        self.band_plan
+            .bands()
+            .iter()
+            .filter_map(|b| by_band.get(b.name.as_str()).map(|spots| (&b.name, spots)))
+            .map(|(band, spots)| {
+                let mut skimmers: HashMap<&str, SkimmerReport> = HashMap::new();
+                for spot in spots {
+                    let regions = regions_cache
+                        .entry(&spot.spotter)
+                        .or_insert_with(|| self.regions_of(&spot.spotter));
+                    skimmers
+                        .entry(&spot.spotter)
+                        .and_modify(|r| r.update(spot))
+                        .or_insert_with(|| SkimmerReport::new(spot, regions.clone()));
+                }
+                let mut skimmers: Vec<SkimmerReport> = skimmers.into_values().collect();
+                skimmers.sort_by(|a, b| {
+                    b.max_snr_db
+                        .cmp(&a.max_snr_db)
+                        .then_with(|| a.spotter.cmp(&b.spotter))
+                });
+                let mut frequencies: Vec<f64> =
+                    spots.iter().map(|s| s.freq.get::<kilohertz>()).collect();
+                frequencies.sort_by(f64::total_cmp);
+                frequencies.dedup();
+                let mut wpm: Vec<u32> = spots.iter().map(|s| s.wpm).collect();
+                wpm.sort_unstable();
+                wpm.dedup();
+                let mut references: Vec<ProgramReference> = spots
+                    .iter()
+                    .flat_map(|s| s.references.iter().cloned())
+                    .collect();
+                references.sort_unstable();
+                references.dedup();
+                CallBandActivity {
+                    band: band.clone(),
+                    first_seen: spots.iter().map(|s| s.timestamp).min().unwrap_or_default(),
+                    last_seen: spots.iter().map(|s| s.timestamp).max().unwrap_or_default(),
+                    frequencies,
+                    wpm,
+                    references,
+                    skimmers,
+                }
+            })
+            .collect()
+    }
+
+    /// Estimates the frequency offset of all skimmers from the current spots.
+    pub fn estimate_calibration(&self) -> HashMap<String, Calibration> {
+        calibration::estimate(self.spots.iter())
+    }
+
+    pub fn set_calibration(&mut self, calibration: HashMap<String, Calibration>) {
+        self.calibration = calibration;
+    }
+
+    pub fn get_calibration(&self, spotter: &str) -> Option<Calibration> {
+        self.calibration.get(spotter).copied()
     }
 }
 
@@ -265,12 +918,12 @@ mod tests {
 
     #[fixture]
     fn empty_db() -> SpotDB {
-        SpotDB::new()
+        SpotDB::default()
     }
 
     #[rstest]
     fn init_db() {
-        let db = SpotDB::new();
+        let db = SpotDB::default();
         assert_eq!(db.spots_in_db(), 0);
     }
 
@@ -309,9 +962,513 @@ mod tests {
         empty_db.add_spot("HB9HUS", "DL1ABC", f, "CW", 10, 25, "CQ", Utc::now());
         let r = empty_db.get_region("europe");
         if let Some(reg) = r {
-            assert_eq!(reg.spotter_spots.len(), 1)
+            assert_eq!(reg.num_spots(), 1)
         } else {
             panic!("did not get a region")
         }
     }
+
+    #[rstest]
+    fn db_get_call_activity(mut empty_db: SpotDB) {
+        empty_db.add_region(
+            "europe".to_string(),
+            vec!["HB".to_string(), "DL".to_string()],
+        );
+        let now = Utc::now();
+        let earlier = now - Duration::from_secs(120);
+        let f20 = Frequency::new::<kilohertz>(14025.0);
+        let f40 = Frequency::new::<kilohertz>(7010.0);
+        empty_db.add_spot("HB9HUS", "K1ABC", f20, "CW", 5, 25, "CQ", earlier);
+        empty_db.add_spot("HB9HUS", "K1ABC", f20, "CW", 12, 25, "CQ", now);
+        empty_db.add_spot("W1XYZ", "K1ABC", f20, "CW", 20, 24, "CQ", now);
+        empty_db.add_spot("DL1ABC", "K1ABC", f40, "CW", 8, 25, "CQ", now);
+        empty_db.add_spot("DL1ABC", "OTHER", f40, "CW", 8, 25, "CQ", now);
+
+        let activity = empty_db.get_call_activity("K1ABC", &[]);
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].band, "40m");
+        let twenty = &activity[1];
+        assert_eq!(twenty.band, "20m");
+        assert_eq!(twenty.first_seen, earlier);
+        assert_eq!(twenty.skimmers.len(), 2);
+        assert_eq!(twenty.skimmers[0].spotter, "W1XYZ");
+        assert!(twenty.skimmers[0].regions.is_empty());
+        let hb = &twenty.skimmers[1];
+        assert_eq!(hb.num_spots, 2);
+        assert_eq!(hb.snr_db, 12);
+        assert_eq!(hb.regions, vec!["europe".to_string()]);
+    }
+
+    #[rstest]
+    fn db_out_of_band(mut empty_db: SpotDB) {
+        let f = Frequency::new::<kilohertz>(14500.0);
+        empty_db.add_spot("HB9HUS", "HB9CL", f, "CW", 10, 25, "CQ", Utc::now());
+        assert_eq!(empty_db.spots_in_db(), 0);
+        assert_eq!(empty_db.out_of_band_spots().len(), 1);
+        assert_eq!(empty_db.out_of_band_spots()[0].band, None);
+    }
+
+    #[rstest]
+    fn db_out_of_segment(mut empty_db: SpotDB) {
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        empty_db.add_spot("HB9HUS", "HB9CL", khz(14025.0), "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14025.0), "FT8", 10, 0, "CQ", now);
+        empty_db.add_spot("HB9HUS", "W1XYZ", khz(14074.0), "FT8", 10, 0, "CQ", now);
+        // still activity, only flagged
+        assert_eq!(empty_db.spots_in_db(), 3);
+        let spotted: Vec<&str> = empty_db
+            .out_of_segment_spots()
+            .iter()
+            .map(|s| s.spotted.as_str())
+            .collect();
+        assert_eq!(spotted, vec!["K1ABC"]);
+        empty_db.remove_spots_before(now + Duration::from_secs(1));
+        assert!(empty_db.out_of_segment_spots().is_empty());
+    }
+
+    #[rstest]
+    fn region_activity_buckets(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        empty_db.add_spot("HB9HUS", "NOW", f, "CW", 10, 25, "CQ", now);
+        let three_min = now - Duration::from_secs(3 * 60);
+        empty_db.add_spot("HB9HUS", "THREE", f, "CW", 10, 25, "CQ", three_min);
+        let twenty_min = now - Duration::from_secs(20 * 60);
+        empty_db.add_spot("HB9HUS", "TWENTY", f, "CW", 10, 25, "CQ", twenty_min);
+        let old = now - Duration::from_secs(40 * 60);
+        empty_db.add_spot("HB9HUS", "OLD", f, "CW", 10, 25, "CQ", old);
+
+        let windows: Vec<Duration> = [2, 10, 30]
+            .iter()
+            .map(|m| Duration::from_secs(m * 60))
+            .collect();
+        let region = empty_db.get_region("europe").unwrap();
+        let query = ActivityQuery {
+            windows: windows.to_vec(),
+            ..Default::default()
+        };
+        let activity = region.get_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let twenty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "20m")
+            .unwrap();
+        let calls: Vec<(u64, Vec<String>)> = twenty
+            .buckets
+            .iter()
+            .map(|b| (b.window_mins, b.calls.clone()))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (2, vec!["NOW".to_string()]),
+                (10, vec!["THREE".to_string()]),
+                (30, vec!["TWENTY".to_string()]),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn region_aggregate_expiry(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        let old = now - Duration::from_secs(20 * 60);
+        empty_db.add_spot("HB9HUS", "K1ABC", f, "CW", 10, 25, "CQ", old);
+        empty_db.add_spot("HB9HUS", "K1ABC", f, "CW", 15, 25, "CQ", now);
+        empty_db.add_spot("HB9XYZ", "K1OLD", f, "CW", 10, 25, "CQ", old);
+        empty_db.cleanup_old_spots(Duration::from_secs(10 * 60));
+
+        let windows = [Duration::from_secs(60), Duration::from_secs(30 * 60)];
+        let region = empty_db.get_region("europe").unwrap();
+        let query = ActivityQuery {
+            windows: windows.to_vec(),
+            ..Default::default()
+        };
+        let activity = region.get_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        assert_eq!(activity.spotters, vec!["HB9HUS".to_string()]);
+        assert_eq!(activity.call_info["K1ABC"].db, vec![15]);
+        assert!(!activity.call_info.contains_key("K1OLD"));
+    }
+
+    #[rstest]
+    fn region_readable_while_db_locked() {
+        let shared = SharedDB::new(SpotDB::default());
+        shared
+            .write()
+            .add_region("europe".to_string(), vec!["HB".to_string()]);
+        let (region, band_plan) = {
+            let db = shared.read();
+            (
+                db.get_region("europe").unwrap().clone(),
+                db.band_plan().clone(),
+            )
+        };
+
+        let mut db = shared.write();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("HB9HUS", "K1ABC", f, "CW", 10, 25, "CQ", Utc::now());
+        let query = ActivityQuery {
+            windows: vec![Duration::from_secs(60)],
+            ..Default::default()
+        };
+        let activity = region.get_band_activities(&band_plan, &query, &HashSet::new());
+        assert_eq!(activity.spotters, vec!["HB9HUS".to_string()]);
+        assert!(activity.call_info.contains_key("K1ABC"));
+    }
+
+    #[rstest]
+    fn db_max_spots(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        empty_db.set_max_spots(Some(10));
+        let start = Utc::now() - Duration::from_secs(3600);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        for i in 0..25 {
+            let t = start + Duration::from_secs(i * 60);
+            empty_db.add_spot("HB9HUS", &format!("K{i}ABC"), f, "CW", 10, 25, "CQ", t);
+        }
+        let kept = empty_db.spots_in_db();
+        assert!(kept <= 10);
+        assert_eq!(empty_db.evicted_spots(), 25 - kept as u64);
+        assert_eq!(empty_db.get_region("europe").unwrap().num_spots(), kept);
+        // the newest spots survive
+        assert_eq!(empty_db.spots.by_call("K24ABC").count(), 1);
+        assert_eq!(empty_db.spots.by_call("K0ABC").count(), 0);
+
+        let memory = empty_db.memory_usage();
+        assert!(memory.spots > 0 && memory.indexes > 0 && memory.regions > 0);
+        assert_eq!(
+            memory.total,
+            memory.spots
+                + memory.indexes
+                + memory.signals
+                + memory.regions
+                + memory.out_of_band
+                + memory.beacons
+        );
+
+        // out-of-band and beacon spots count against the same cap
+        let later = start + Duration::from_secs(30 * 60);
+        for i in 0..10 {
+            let t = later + Duration::from_secs(i * 60);
+            let out_of_band = Frequency::new::<kilohertz>(14500.0);
+            empty_db.add_spot(
+                "HB9HUS",
+                &format!("N{i}ABC"),
+                out_of_band,
+                "CW",
+                10,
+                25,
+                "CQ",
+                t,
+            );
+            empty_db.add_spot("HB9HUS", &format!("N{i}ABC/B"), f, "CW", 10, 25, "CQ", t);
+        }
+        assert!(empty_db.num_stored_spots() <= 10);
+        assert_eq!(empty_db.spots_in_db(), 0);
+        assert_eq!(
+            empty_db.evicted_spots(),
+            45 - empty_db.num_stored_spots() as u64
+        );
+    }
+
+    #[rstest]
+    fn region_hides_busted_calls(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        for spotter in ["HB9AAA", "HB9BBB", "HB9CCC"] {
+            empty_db.add_spot(spotter, "K1ABC", f, "CW", 20, 25, "CQ", now);
+        }
+        empty_db.add_spot("HB9DDD", "K1ABD", f, "CW", 3, 25, "CQ", now);
+
+        let region = empty_db.get_region("europe").unwrap();
+        let busted = empty_db.busted_calls();
+        let calls = |query: &ActivityQuery| {
+            let activity = region.compute_band_activities(empty_db.band_plan(), query, &busted);
+            let mut calls: Vec<(String, bool)> = activity
+                .call_info
+                .iter()
+                .map(|(call, info)| (call.clone(), info.busted))
+                .collect();
+            calls.sort();
+            calls
+        };
+        let mut query = ActivityQuery {
+            windows: vec![Duration::from_secs(60)],
+            ..Default::default()
+        };
+        assert_eq!(calls(&query), vec![("K1ABC".to_string(), false)]);
+        query.include_busted = true;
+        assert_eq!(
+            calls(&query),
+            vec![("K1ABC".to_string(), false), ("K1ABD".to_string(), true)]
+        );
+    }
+
+    #[rstest]
+    #[case("CQ", MsgType::Cq)]
+    #[case("DX", MsgType::Dx)]
+    #[case("NCDXF", MsgType::Ncdxf)]
+    #[case("BEACON", MsgType::Beacon)]
+    #[case("", MsgType::Other)]
+    fn msg_type(#[case] msg: &str, #[case] expected: MsgType) {
+        assert_eq!(MsgType::from_msg(msg), expected);
+    }
+
+    #[rstest]
+    fn region_activity_per_mode(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14025.0), "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14074.0), "FT8", 10, 0, "CQ", now);
+        empty_db.add_spot("HB9HUS", "W1XYZ", khz(14080.0), "RTTY", 10, 0, "DX", now);
+
+        let region = empty_db.get_region("europe").unwrap();
+        let mut query = ActivityQuery {
+            windows: vec![Duration::from_secs(60)],
+            ..Default::default()
+        };
+        let activity =
+            region.compute_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let twenty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "20m")
+            .unwrap();
+        assert_eq!(twenty.buckets[0].calls, vec!["K1ABC", "W1XYZ"]);
+        let modes: Vec<(&str, &[String])> = twenty
+            .modes
+            .iter()
+            .map(|m| (m.mode.as_str(), m.buckets[0].calls.as_slice()))
+            .collect();
+        assert_eq!(
+            modes,
+            vec![
+                ("CW", &["K1ABC".to_string()][..]),
+                ("FT8", &["K1ABC".to_string()][..]),
+                ("RTTY", &["W1XYZ".to_string()][..]),
+            ]
+        );
+        assert_eq!(activity.call_info["K1ABC"].modes, vec!["CW", "FT8"]);
+        assert_eq!(activity.call_info["W1XYZ"].msg_types, vec![MsgType::Dx]);
+
+        query.modes = vec!["CW".to_string(), "FT8".to_string()];
+        let activity =
+            region.compute_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let twenty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "20m")
+            .unwrap();
+        assert_eq!(twenty.buckets[0].calls, vec!["K1ABC"]);
+        assert!(!activity.call_info.contains_key("W1XYZ"));
+    }
+
+    #[rstest]
+    fn region_call_info_of_selected_spots(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["HB".to_string()]);
+        let now = Utc::now();
+        let earlier = now - Duration::from_secs(25 * 60);
+        let khz = Frequency::new::<kilohertz>;
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14025.0), "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14074.0), "FT8", -5, 0, "CQ", now);
+        empty_db.add_spot("HB9HUS", "K1ABC", khz(14030.0), "CW", 20, 30, "DX", earlier);
+
+        let region = empty_db.get_region("europe").unwrap();
+        let query = ActivityQuery {
+            windows: vec![Duration::from_secs(5 * 60)],
+            modes: vec!["CW".to_string()],
+            ..Default::default()
+        };
+        let activity =
+            region.compute_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let info = &activity.call_info["K1ABC"];
+        assert_eq!(info.frequencies, vec![14025.0]);
+        assert_eq!(info.modes, vec!["CW"]);
+        assert_eq!(info.wpm, vec![25]);
+        assert_eq!(info.db, vec![10]);
+        assert_eq!(info.msg_types, vec![MsgType::Cq]);
+    }
+
+    #[rstest]
+    fn region_paths_and_distance_filter(mut empty_db: SpotDB) {
+        let mut locations = Locations::default();
+        locations
+            .add_country_file(
+                "Germany: 14: 28: EU: 51.0: -10.0: -1.0: DL:\n    DL;\n\
+                 United States: 05: 08: NA: 37.53: 91.67: 5.0: K:\n    K,W;\n\
+                 Japan: 25: 45: AS: 36.4: -138.38: -9.0: JA:\n    JA;\n",
+            )
+            .unwrap();
+        locations.add_skimmers("DL1AAA JO62qm").unwrap();
+        empty_db.set_locations(locations);
+        empty_db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        empty_db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("DL1AAA", "JA1ABC", f, "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("DL2BBB", "DL3CCC", f, "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("DL1AAA", "VK2ABC", f, "CW", 10, 25, "CQ", now);
+
+        let skimmer = &empty_db.get_call_activity("K1ABC", &[])[0].skimmers[0];
+        assert!(
+            skimmer
+                .distance_km
+                .is_some_and(|d| (7000..8000).contains(&d))
+        );
+        assert!(skimmer.bearing_deg.is_some_and(|b| (270..330).contains(&b)));
+        assert_eq!(
+            empty_db.get_call_activity("VK2ABC", &[])[0].skimmers[0].distance_km,
+            None
+        );
+
+        let region = empty_db.get_region("europe").unwrap();
+        let mut query = ActivityQuery {
+            windows: vec![Duration::from_secs(60)],
+            ..Default::default()
+        };
+        let twenty = |query: &ActivityQuery| {
+            region
+                .compute_band_activities(empty_db.band_plan(), query, &HashSet::new())
+                .band_activities
+                .into_iter()
+                .find(|a| a.band == "20m")
+                .unwrap()
+        };
+        let activity = twenty(&query);
+        assert_eq!(activity.buckets[0].calls.len(), 4);
+        let longest = activity.longest_path.unwrap();
+        assert_eq!(
+            (longest.spotter.as_str(), longest.spotted.as_str()),
+            ("DL1AAA", "JA1ABC")
+        );
+
+        query.min_distance_km = Some(1000);
+        query.max_distance_km = Some(8000);
+        let activity = twenty(&query);
+        assert_eq!(activity.buckets[0].calls, vec!["K1ABC"]);
+        assert_eq!(activity.longest_path.unwrap().spotted, "K1ABC");
+    }
+
+    #[rstest]
+    fn huge_activity_window(mut empty_db: SpotDB) {
+        empty_db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        empty_db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", Utc::now());
+        let query = ActivityQuery {
+            windows: vec![Duration::from_secs(u64::MAX)],
+            ..Default::default()
+        };
+        let region = empty_db.get_region("europe").unwrap();
+        let activity = region.get_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let twenty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "20m")
+            .unwrap();
+        assert_eq!(twenty.buckets[0].calls, vec!["K1ABC"]);
+        assert_eq!(
+            window_start(Utc::now(), Duration::from_secs(u64::MAX)),
+            DateTime::<Utc>::MIN_UTC
+        );
+    }
+
+    #[rstest]
+    fn spot_memory_counts_references(mut empty_db: SpotDB) {
+        let now = Utc::now();
+        let schedule = format!(
+            r#"[{{"call": "K1ABC", "program": "POTA", "reference": "K-0001",
+                "start": "{}", "end": "{}"}}]"#,
+            (now - Duration::from_secs(600)).to_rfc3339(),
+            (now + Duration::from_secs(600)).to_rfc3339()
+        );
+        let mut activations = Activations::default();
+        activations.add_schedule(&schedule).unwrap();
+        empty_db.set_activations(activations);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        empty_db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", now);
+        empty_db.add_spot("DL1AAA", "W1XYZ", f, "CW", 10, 25, "CQ", now);
+        let bytes = |call: &str| empty_db.spots.by_call(call).next().unwrap().memory_bytes();
+        assert!(bytes("K1ABC") >= bytes("W1XYZ") + size_of::<ProgramReference>());
+    }
+
+    #[rstest]
+    fn region_and_call_program_filter(mut empty_db: SpotDB) {
+        let now = Utc::now();
+        let schedule = format!(
+            r#"[{{"call": "K1ABC", "program": "POTA", "reference": "K-0001",
+                "start": "{}", "end": "{}"}}]"#,
+            (now - Duration::from_secs(600)).to_rfc3339(),
+            (now + Duration::from_secs(600)).to_rfc3339()
+        );
+        let mut activations = Activations::default();
+        activations.add_schedule(&schedule).unwrap();
+        empty_db.set_activations(activations);
+        empty_db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        // spotted before the activation started
+        empty_db.add_spot(
+            "DL1AAA",
+            "K1ABC",
+            f,
+            "CW",
+            10,
+            25,
+            "CQ",
+            now - Duration::from_secs(900),
+        );
+        empty_db.add_spot("DL1AAA", "K1ABC/P", f, "CW", 10, 25, "CQ", now);
+        // the same call on another band before the activation
+        let forty = Frequency::new::<kilohertz>(7025.0);
+        empty_db.add_spot(
+            "DL1AAA",
+            "K1ABC/P",
+            forty,
+            "CW",
+            10,
+            25,
+            "CQ",
+            now - Duration::from_secs(900),
+        );
+        empty_db.add_spot("DL1AAA", "W1XYZ", f, "CW", 10, 25, "CQ", now);
+
+        let region = empty_db.get_region("europe").unwrap();
+        let query = ActivityQuery {
+            windows: vec![Duration::from_secs(3600)],
+            programs: vec![Program::Pota],
+            ..Default::default()
+        };
+        let activity =
+            region.compute_band_activities(empty_db.band_plan(), &query, &HashSet::new());
+        let twenty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "20m")
+            .unwrap();
+        assert_eq!(twenty.buckets[0].calls, vec!["K1ABC/P"]);
+        let forty = activity
+            .band_activities
+            .iter()
+            .find(|a| a.band == "40m")
+            .unwrap();
+        assert!(forty.buckets[0].calls.is_empty());
+        assert_eq!(
+            activity.call_info["K1ABC/P"].references[0].reference,
+            "K-0001"
+        );
+
+        assert_eq!(empty_db.get_call_activity("K1ABC", &[]).len(), 1);
+        assert!(
+            empty_db
+                .get_call_activity("K1ABC", &[Program::Pota])
+                .is_empty()
+        );
+        let sota = empty_db.get_call_activity("K1ABC/P", &[Program::Sota]);
+        assert!(sota.is_empty());
+        assert_eq!(empty_db.get_activations("K1ABC", now).len(), 1);
+    }
 }
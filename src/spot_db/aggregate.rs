@@ -0,0 +1,416 @@
+use super::memory::reference_bytes;
+use super::{
+    ActivityBucket, ActivityQuery, BandActivity, CallInfo, LongestPath, ModeActivity, MsgType,
+    Spot, window_start,
+};
+use crate::activations::{Program, ProgramReference};
+use crate::bands::BandPlan;
+use crate::geo::Path;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+use uom::si::frequency::hertz;
+
+/// A sorted multiset, counts how often each value was added.
+#[derive(Debug)]
+pub(super) struct Counter<K: Ord>(BTreeMap<K, usize>);
+
+impl<K: Ord> Default for Counter<K> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<K: Ord + Clone> Counter<K> {
+    pub(super) fn add(&mut self, key: K) {
+        *self.0.entry(key).or_insert(0) += 1;
+    }
+
+    pub(super) fn remove(&mut self, key: &K) {
+        if let Some(count) = self.0.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.0.remove(key);
+            }
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(super) fn first(&self) -> Option<&K> {
+        self.0.keys().next()
+    }
+
+    /// Values from the largest down.
+    pub(super) fn iter_rev(&self) -> impl Iterator<Item = &K> {
+        self.0.keys().rev()
+    }
+
+    /// Every value as often as it was added, from the smallest up.
+    fn iter_all(&self) -> impl Iterator<Item = &K> {
+        self.0
+            .iter()
+            .flat_map(|(key, count)| std::iter::repeat_n(key, *count))
+    }
+
+    pub(super) fn memory_bytes(&self) -> usize {
+        self.0.len() * size_of::<(K, usize)>()
+    }
+}
+
+/// Buckets the calls by the shortest of the sorted `windows` containing
+/// their newest spot, calls older than all windows are left out.
+fn bucketize(
+    windows: &[Duration],
+    newest: &HashMap<&str, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Vec<ActivityBucket> {
+    let mut buckets: Vec<ActivityBucket> = windows
+        .iter()
+        .map(|w| ActivityBucket {
+            window_mins: w.as_secs() / 60,
+            calls: Vec::new(),
+        })
+        .collect();
+    for (call, last_seen) in newest {
+        if let Some(i) = windows
+            .iter()
+            .position(|w| window_start(now, *w) < *last_seen)
+        {
+            buckets[i].calls.push(call.to_string());
+        }
+    }
+    for bucket in &mut buckets {
+        bucket.calls.sort_unstable();
+    }
+    buckets
+}
+
+/// What the activity of a region needs to know of a spot, newest last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SpotTime {
+    timestamp: DateTime<Utc>,
+    distance_km: Option<u32>,
+    snr_db: i32,
+    freq_hz: i64,
+    wpm: u32,
+    msg_type: MsgType,
+    // programs activated
+    programs: Vec<Program>,
+    references: Vec<ProgramReference>,
+}
+// path, spotter, spotted, programs activated
+type SpotPath = (Path, String, String, Vec<Program>);
+
+#[derive(Debug, Default)]
+struct ModeAggregate {
+    // call -> spot times
+    calls: HashMap<String, Counter<SpotTime>>,
+    paths: Counter<SpotPath>,
+}
+
+/// Sorted programs of the references the spotted station activated.
+fn spot_programs(spot: &Spot) -> Vec<Program> {
+    let mut programs: Vec<Program> = spot.references.iter().map(|r| r.program).collect();
+    programs.sort_unstable();
+    programs.dedup();
+    programs
+}
+
+fn spot_time(spot: &Spot) -> SpotTime {
+    SpotTime {
+        timestamp: spot.timestamp,
+        distance_km: spot.path.map(|p| p.distance_km),
+        snr_db: spot.snr_db,
+        freq_hz: spot.freq.get::<hertz>().round() as i64,
+        wpm: spot.wpm,
+        msg_type: spot.msg_type(),
+        programs: spot_programs(spot),
+        references: spot.references.clone(),
+    }
+}
+
+fn spot_path(spot: &Spot) -> Option<SpotPath> {
+    spot.path.map(|path| {
+        (
+            path,
+            spot.spotter.clone(),
+            spot.spotted.clone(),
+            spot_programs(spot),
+        )
+    })
+}
+
+/// Whether a spot activating `activated` passes the `programs` filter,
+/// always true if there are none.
+fn activates(activated: &[Program], programs: &[Program]) -> bool {
+    programs.is_empty() || activated.iter().any(|p| programs.contains(p))
+}
+
+/// Details of the selected spots of a call, for its call info.
+#[derive(Default)]
+struct CallDetails<'a> {
+    freq_hz: BTreeSet<i64>,
+    wpm: BTreeSet<u32>,
+    db: BTreeSet<i32>,
+    modes: BTreeSet<&'a str>,
+    msg_types: BTreeSet<MsgType>,
+    references: BTreeSet<&'a ProgramReference>,
+}
+
+impl<'a> CallDetails<'a> {
+    fn add(&mut self, mode: &'a str, time: &'a SpotTime) {
+        self.freq_hz.insert(time.freq_hz);
+        self.wpm.insert(time.wpm);
+        self.db.insert(time.snr_db);
+        self.modes.insert(mode);
+        self.msg_types.insert(time.msg_type);
+        self.references.extend(&time.references);
+    }
+
+    fn call_info(self, busted: bool) -> CallInfo {
+        CallInfo {
+            frequencies: self
+                .freq_hz
+                .into_iter()
+                .map(|hz| hz as f64 / 1_000.0)
+                .collect(),
+            wpm: self.wpm.into_iter().collect(),
+            db: self.db.into_iter().collect(),
+            modes: self.modes.into_iter().map(ToString::to_string).collect(),
+            msg_types: self.msg_types.into_iter().collect(),
+            busted,
+            references: self.references.into_iter().cloned().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Activity of a region, updated whenever a spot is added or expires so a
+/// request only has to walk the calls, not all spots.
+#[derive(Debug, Default)]
+pub(super) struct RegionAggregate {
+    // band -> mode -> calls and paths
+    bands: HashMap<String, HashMap<String, ModeAggregate>>,
+    spotters: HashMap<String, usize>,
+}
+
+impl RegionAggregate {
+    pub(super) fn add(&mut self, spot: &Spot) {
+        *self.spotters.entry(spot.spotter.clone()).or_insert(0) += 1;
+        let Some(band) = &spot.band else {
+            return;
+        };
+        let mode = self
+            .bands
+            .entry(band.clone())
+            .or_default()
+            .entry(spot.mode.clone())
+            .or_default();
+        mode.calls
+            .entry(spot.spotted.clone())
+            .or_default()
+            .add(spot_time(spot));
+        if let Some(path) = spot_path(spot) {
+            mode.paths.add(path);
+        }
+    }
+
+    pub(super) fn remove(&mut self, spot: &Spot) {
+        if let Some(count) = self.spotters.get_mut(&spot.spotter) {
+            *count -= 1;
+            if *count == 0 {
+                self.spotters.remove(&spot.spotter);
+            }
+        }
+        let Some(band) = &spot.band else {
+            return;
+        };
+        if let Some(modes) = self.bands.get_mut(band)
+            && let Some(mode) = modes.get_mut(&spot.mode)
+            && let Some(times) = mode.calls.get_mut(&spot.spotted)
+        {
+            times.remove(&spot_time(spot));
+            if let Some(path) = spot_path(spot) {
+                mode.paths.remove(&path);
+            }
+            if times.is_empty() {
+                mode.calls.remove(&spot.spotted);
+                if mode.calls.is_empty() {
+                    modes.remove(&spot.mode);
+                }
+            }
+        }
+    }
+
+    /// Puts every call into the shortest of the sorted query windows
+    /// containing its newest spot on that band, over all selected modes and
+    /// per mode. Only spots within the distance limits of the query and
+    /// activating one of its programs count.
+    pub(super) fn band_activities(
+        &self,
+        band_plan: &BandPlan,
+        query: &ActivityQuery,
+        busted: &HashSet<String>,
+        now: DateTime<Utc>,
+    ) -> (Vec<BandActivity>, Vec<String>, HashMap<String, CallInfo>) {
+        let windows: &[Duration] = &query.windows;
+        // spots older than the longest window are in no bucket
+        let oldest = windows
+            .iter()
+            .max()
+            .map_or(DateTime::<Utc>::MAX_UTC, |w| window_start(now, *w));
+        let mut details: HashMap<&str, CallDetails> = HashMap::new();
+        let band_activities = band_plan
+            .bands()
+            .iter()
+            .map(|band| {
+                let mut newest: HashMap<&str, DateTime<Utc>> = HashMap::new();
+                let mut modes: Vec<ModeActivity> = Vec::new();
+                let mut longest_path: Option<&SpotPath> = None;
+                for (mode, aggregate) in self.bands.get(&band.name).into_iter().flatten() {
+                    if !query.modes.is_empty() && !query.modes.contains(mode) {
+                        continue;
+                    }
+                    let hidden = |call: &str| busted.contains(call) && !query.include_busted;
+                    let mut mode_newest: HashMap<&str, DateTime<Utc>> = HashMap::new();
+                    for (call, times) in &aggregate.calls {
+                        if hidden(call) {
+                            continue;
+                        }
+                        let mut selected = times
+                            .iter_rev()
+                            .take_while(|t| t.timestamp > oldest)
+                            .filter(|t| {
+                                query.matches_distance(t.distance_km)
+                                    && activates(&t.programs, &query.programs)
+                            })
+                            .peekable();
+                        let Some(last_seen) = selected.peek().map(|t| t.timestamp) else {
+                            continue;
+                        };
+                        let call_details = details.entry(call).or_default();
+                        selected.for_each(|t| call_details.add(mode, t));
+                        mode_newest.insert(call, last_seen);
+                        newest
+                            .entry(call)
+                            .and_modify(|t| *t = (*t).max(last_seen))
+                            .or_insert(last_seen);
+                    }
+                    let mode_longest =
+                        aggregate
+                            .paths
+                            .iter_rev()
+                            .find(|(path, _, spotted, activated)| {
+                                !hidden(spotted)
+                                    && query.matches_distance(Some(path.distance_km))
+                                    && activates(activated, &query.programs)
+                            });
+                    longest_path = longest_path.max(mode_longest);
+                    let buckets = bucketize(windows, &mode_newest, now);
+                    if buckets.iter().any(|b| !b.calls.is_empty()) {
+                        modes.push(ModeActivity {
+                            mode: mode.clone(),
+                            buckets,
+                        });
+                    }
+                }
+                modes.sort_unstable_by(|a, b| a.mode.cmp(&b.mode));
+                let buckets = bucketize(windows, &newest, now);
+                BandActivity {
+                    band: band.name.clone(),
+                    buckets,
+                    modes,
+                    longest_path: longest_path.map(|(path, spotter, spotted, _)| LongestPath {
+                        spotter: spotter.clone(),
+                        spotted: spotted.clone(),
+                        distance_km: path.distance_km,
+                        bearing_deg: path.bearing_deg,
+                    }),
+                }
+            })
+            .collect();
+        let mut spotters: Vec<String> = self.spotters.keys().cloned().collect();
+        spotters.sort_unstable();
+        // from the same spots as the buckets, over all bands
+        let call_info = details
+            .into_iter()
+            .map(|(call, details)| (call.to_string(), details.call_info(busted.contains(call))))
+            .collect();
+        (band_activities, spotters, call_info)
+    }
+
+    /// Newest spot of every call on `band`, over all modes.
+    pub(super) fn newest_on_band(&self, band: &str) -> HashMap<&str, DateTime<Utc>> {
+        let mut newest: HashMap<&str, DateTime<Utc>> = HashMap::new();
+        for mode in self.bands.get(band).into_iter().flat_map(HashMap::values) {
+            for (call, times) in &mode.calls {
+                if let Some(last_seen) = times.iter_rev().next().map(|t| t.timestamp) {
+                    newest
+                        .entry(call)
+                        .and_modify(|t| *t = (*t).max(last_seen))
+                        .or_insert(last_seen);
+                }
+            }
+        }
+        newest
+    }
+
+    /// Band, call, timestamp and SNR of every spot since `start`.
+    pub(super) fn snr_since(
+        &self,
+        start: DateTime<Utc>,
+    ) -> impl Iterator<Item = (&str, &str, DateTime<Utc>, i32)> {
+        self.bands.iter().flat_map(move |(band, modes)| {
+            modes.values().flat_map(move |mode| {
+                mode.calls.iter().flat_map(move |(call, times)| {
+                    times
+                        .iter_all()
+                        .filter(move |t| t.timestamp >= start)
+                        .map(move |t| (band.as_str(), call.as_str(), t.timestamp, t.snr_db))
+                })
+            })
+        })
+    }
+
+    /// Estimated heap usage in bytes.
+    pub(super) fn memory_bytes(&self) -> usize {
+        let bands: usize = self
+            .bands
+            .values()
+            .flat_map(HashMap::values)
+            .map(|mode| {
+                let calls: usize = mode
+                    .calls
+                    .iter()
+                    .map(|(call, times)| {
+                        let details: usize = times
+                            .0
+                            .keys()
+                            .map(|t| {
+                                t.programs.capacity() * size_of::<Program>()
+                                    + t.references.iter().map(reference_bytes).sum::<usize>()
+                            })
+                            .sum();
+                        size_of::<String>() + call.capacity() + times.memory_bytes() + details
+                    })
+                    .sum();
+                let paths: usize = mode
+                    .paths
+                    .0
+                    .keys()
+                    .map(|(_, spotter, spotted, _)| spotter.capacity() + spotted.capacity())
+                    .sum();
+                calls + paths + mode.paths.memory_bytes()
+            })
+            .sum();
+        let spotters: usize = self
+            .spotters
+            .keys()
+            .map(|s| size_of::<(String, usize)>() + s.capacity())
+            .sum();
+        bands + spotters
+    }
+}
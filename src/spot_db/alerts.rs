@@ -0,0 +1,235 @@
+use super::{Spot, SpotDB, WorkedStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uom::si::frequency::kilohertz;
+
+/// Alerts kept for polling, the oldest are dropped beyond this.
+const MAX_ALERTS: usize = 1000;
+
+/// What a user wants to be alerted about.
+#[derive(Debug, Clone)]
+pub struct AlertPreferences {
+    /// all bands / modes if empty
+    pub bands: Vec<String>,
+    pub modes: Vec<String>,
+    /// skimmers that must hear a call before it is alerted
+    pub min_skimmers: usize,
+}
+
+/// A spotted call in an entity, band and mode slot the user still needs.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Alert {
+    pub id: u64,
+    pub user: String,
+    pub call: String,
+    pub entity: String,
+    pub band: String,
+    pub mode: String,
+    pub status: WorkedStatus,
+    pub frequency: f64, // kHz, of the spot raising the alert
+    pub num_skimmers: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+// user, call, band, mode
+pub(super) type AlertKey = (String, String, String, String);
+
+impl AlertPreferences {
+    fn matches(&self, band: &str, mode: &str) -> bool {
+        (self.bands.is_empty() || self.bands.iter().any(|b| b.eq_ignore_ascii_case(band)))
+            && (self.modes.is_empty() || self.modes.iter().any(|m| m.eq_ignore_ascii_case(mode)))
+    }
+}
+
+impl SpotDB {
+    /// Alerts for `user` on spots arriving from now on. Users without a log
+    /// are never alerted.
+    pub fn set_alert_preferences(&mut self, user: String, preferences: AlertPreferences) {
+        self.alert_preferences.insert(user, preferences);
+    }
+
+    /// Raises an alert for every user who needs the slot of `spot`, once the
+    /// call has been heard there by enough skimmers. A slot is checked once
+    /// while it has spots, so a call is not alerted again on every spot.
+    pub(super) fn check_alerts(&mut self, spot: &Spot) {
+        let Some(band) = &spot.band else {
+            return;
+        };
+        let mut skimmers: Option<HashSet<&str>> = None;
+        let mut entity: Option<Option<String>> = None;
+        let mut alerts = Vec::new();
+        let mut checked = Vec::new();
+        for (user, preferences) in &self.alert_preferences {
+            let Some(log) = self.logs.get(user) else {
+                continue;
+            };
+            if !preferences.matches(band, &spot.mode) {
+                continue;
+            }
+            let key = (
+                user.clone(),
+                spot.spotted.clone(),
+                band.clone(),
+                spot.mode.clone(),
+            );
+            if self.alert_checked.contains_key(&key) {
+                // checked as long as the station keeps being spotted
+                checked.push(key);
+                continue;
+            }
+            let skimmers = skimmers.get_or_insert_with(|| {
+                self.spots
+                    .by_call(&spot.spotted)
+                    .filter(|s| s.band.as_ref() == Some(band) && s.mode == spot.mode)
+                    .map(|s| s.spotter.as_str())
+                    .collect()
+            });
+            if skimmers.len() < preferences.min_skimmers {
+                continue;
+            }
+            let entity = entity.get_or_insert_with(|| self.locate(&spot.spotted).entity);
+            let status = entity
+                .as_ref()
+                .map(|entity| log.status(entity, band, &spot.mode).0);
+            if let (Some(entity), Some(status)) = (entity, status)
+                && status != WorkedStatus::Worked
+            {
+                alerts.push(Alert {
+                    id: 0,
+                    user: user.clone(),
+                    call: spot.spotted.clone(),
+                    entity: entity.clone(),
+                    band: band.clone(),
+                    mode: spot.mode.clone(),
+                    status,
+                    frequency: spot.freq.get::<kilohertz>(),
+                    num_skimmers: skimmers.len(),
+                    timestamp: spot.timestamp,
+                });
+            }
+            checked.push(key);
+        }
+        for key in checked {
+            self.alert_checked
+                .entry(key)
+                .and_modify(|t| *t = (*t).max(spot.timestamp))
+                .or_insert(spot.timestamp);
+        }
+        for mut alert in alerts {
+            self.last_alert_id += 1;
+            alert.id = self.last_alert_id;
+            self.alerts.push_back(alert);
+        }
+        if self.alerts.len() > MAX_ALERTS {
+            self.alerts.drain(..self.alerts.len() - MAX_ALERTS);
+        }
+    }
+
+    /// Alerts of `user` with an id above `since_id`, oldest first. `None` if
+    /// the user gets no alerts.
+    pub fn get_alerts(&self, user: &str, since_id: u64) -> Option<Vec<Alert>> {
+        if !self.alert_preferences.contains_key(user) || !self.logs.contains_key(user) {
+            return None;
+        }
+        Some(
+            self.alerts
+                .iter()
+                .filter(|a| a.id > since_id && a.user == user)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adif::Qso;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+
+    #[rstest]
+    fn needed_alerts() {
+        let mut db = SpotDB::default();
+        let prefixes = |p: &[&str]| p.iter().map(ToString::to_string).collect();
+        db.add_region("japan".to_string(), prefixes(&["JA"]));
+        db.add_region("brazil".to_string(), prefixes(&["PY"]));
+        let qso = Qso {
+            call: "JA2XYZ".to_string(),
+            band: "20m".to_string(),
+            mode: "CW".to_string(),
+            confirmed: false,
+        };
+        db.set_user_log("hb9hus".to_string(), &[qso]);
+        db.set_alert_preferences(
+            "hb9hus".to_string(),
+            AlertPreferences {
+                bands: vec!["20m".to_string(), "40m".to_string()],
+                modes: vec!["cw".to_string()],
+                min_skimmers: 2,
+            },
+        );
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        // worked slot
+        db.add_spot("DL1AAA", "JA1ABC", khz(14025.0), "CW", 10, 25, "CQ", now);
+        db.add_spot("F1BBB", "JA1ABC", khz(14025.0), "CW", 10, 25, "CQ", now);
+        // a single skimmer is not enough
+        db.add_spot("DL1AAA", "PY1ABC", khz(14030.0), "CW", 10, 25, "CQ", now);
+        assert!(db.get_alerts("hb9hus", 0).unwrap().is_empty());
+        db.add_spot("F1BBB", "PY1ABC", khz(14030.1), "CW", 10, 25, "CQ", now);
+        db.add_spot("G1CCC", "PY1ABC", khz(14030.0), "CW", 10, 25, "CQ", now);
+        // new band, but not a band or mode of the preferences
+        for spotter in ["DL1AAA", "F1BBB"] {
+            db.add_spot(spotter, "JA1ABC", khz(21025.0), "CW", 10, 25, "CQ", now);
+            db.add_spot(spotter, "JA1ABC", khz(14074.0), "FT8", 10, 25, "CQ", now);
+        }
+        for spotter in ["DL1AAA", "F1BBB"] {
+            db.add_spot(spotter, "JA1ABC", khz(7025.0), "CW", 10, 25, "CQ", now);
+        }
+
+        let alerts = db.get_alerts("hb9hus", 0).unwrap();
+        let summary: Vec<(&str, &str, WorkedStatus, usize)> = alerts
+            .iter()
+            .map(|a| (a.call.as_str(), a.band.as_str(), a.status, a.num_skimmers))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("PY1ABC", "20m", WorkedStatus::NewOne, 2),
+                ("JA1ABC", "40m", WorkedStatus::NewBand, 2),
+            ]
+        );
+        assert_eq!(db.get_alerts("hb9hus", alerts[0].id).unwrap().len(), 1);
+        assert!(db.get_alerts("nobody", 0).is_none());
+    }
+
+    #[rstest]
+    fn no_alert_again_while_spotted() {
+        let mut db = SpotDB::default();
+        db.add_region("brazil".to_string(), vec!["PY".to_string()]);
+        db.set_user_log("hb9hus".to_string(), &[]);
+        db.set_alert_preferences(
+            "hb9hus".to_string(),
+            AlertPreferences {
+                bands: vec!["20m".to_string()],
+                modes: vec!["cw".to_string()],
+                min_skimmers: 1,
+            },
+        );
+        let start = Utc::now() - chrono::Duration::minutes(30);
+        let minutes = |m: i64| start + chrono::Duration::minutes(m);
+        let f = Frequency::new::<kilohertz>(14030.0);
+        db.add_spot("DL1AAA", "PY1ABC", f, "CW", 10, 25, "CQ", minutes(0));
+        db.add_spot("DL1AAA", "PY1ABC", f, "CW", 10, 25, "CQ", minutes(20));
+        // the first spot is gone, the station is still spotted
+        db.remove_spots_before(minutes(10));
+        db.add_spot("DL1AAA", "PY1ABC", f, "CW", 10, 25, "CQ", minutes(25));
+        assert_eq!(db.get_alerts("hb9hus", 0).unwrap().len(), 1);
+        // alerted again once all its spots are gone
+        db.remove_spots_before(minutes(26));
+        db.add_spot("DL1AAA", "PY1ABC", f, "CW", 10, 25, "CQ", minutes(30));
+        assert_eq!(db.get_alerts("hb9hus", 0).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+use super::{Spot, SpotDB, median};
+use crate::bands::SegmentKind;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uom::si::f64::Frequency;
+use uom::si::frequency::{hertz, kilohertz};
+
+/// A station currently active in the band.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BandSignal {
+    pub frequency: f64, // kHz, most recent spot
+    pub callsign: String,
+    pub mode: String,
+    pub segment: Option<SegmentKind>,
+    pub age_secs: i64,
+    pub snr_db: Option<i32>, // median over all skimmers
+    pub num_skimmers: usize,
+}
+
+/// A free range inside a CW or digital segment.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Gap {
+    pub segment: SegmentKind,
+    pub lower: f64, // kHz
+    pub upper: f64, // kHz
+    pub width_hz: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BandMap {
+    pub band: String,
+    pub signals: Vec<BandSignal>,
+    pub gaps: Vec<Gap>,
+}
+
+/// Bandwidth in Hz a CW signal, or one of a mode not known, occupies: the
+/// spread of the skimmers' frequencies more than the keying itself.
+const CW_BANDWIDTH_HZ: f64 = 300.0;
+
+/// Bandwidth in Hz a signal of `mode` occupies around its frequency.
+fn signal_bandwidth_hz(mode: &str) -> f64 {
+    match mode {
+        "FT8" | "FT4" => 50.0,
+        "PSK31" | "PSK63" => 100.0,
+        "RTTY" => 500.0,
+        _ => CW_BANDWIDTH_HZ,
+    }
+}
+
+/// Finds all ranges between `lower` and `upper` (kHz) that are at least
+/// `min_width` kHz wide and overlap none of the `occupied` ranges.
+fn find_gaps(lower: f64, upper: f64, occupied: &[(f64, f64)], min_width: f64) -> Vec<(f64, f64)> {
+    let mut occupied = occupied.to_vec();
+    occupied.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut gaps = Vec::new();
+    let mut free_from = lower;
+    for (start, end) in occupied {
+        let start = start.min(upper);
+        if start - free_from >= min_width {
+            gaps.push((free_from, start));
+        }
+        free_from = free_from.max(end);
+    }
+    if upper - free_from >= min_width {
+        gaps.push((free_from, upper));
+    }
+    gaps
+}
+
+impl SpotDB {
+    /// All signals in the band `band_name` ordered by frequency, plus the
+    /// clear gaps of at least `min_gap` in its CW and digital sub-bands,
+    /// leaving the bandwidth of every signal's mode around it. If
+    /// `region` is given only spots of skimmers in that region are used.
+    /// `None` if the band or region does not exist.
+    pub fn get_band_map(
+        &self,
+        band_name: &str,
+        region: Option<&str>,
+        min_gap: Frequency,
+    ) -> Option<BandMap> {
+        let band = self.band_plan.find_band_by_name(band_name)?;
+        let spots: Box<dyn Iterator<Item = &Arc<Spot>>> = match region {
+            Some(name) => Box::new(self.region_spots(self.get_region(name)?).into_iter()),
+            None => Box::new(self.spots.in_range(band.lower, band.upper)),
+        };
+        let mut by_call: HashMap<&str, Vec<&Spot>> = HashMap::new();
+        for spot in spots.filter(|s| s.band.as_ref() == Some(&band.name)) {
+            by_call.entry(&spot.spotted).or_default().push(spot);
+        }
+
+        let now = Utc::now();
+        let mut signals: Vec<BandSignal> = by_call
+            .into_iter()
+            .filter_map(|(call, spots)| {
+                let latest = spots.iter().max_by_key(|s| s.timestamp)?;
+                let mut skimmers: Vec<&str> = spots.iter().map(|s| s.spotter.as_str()).collect();
+                skimmers.sort_unstable();
+                skimmers.dedup();
+                let mut snr: Vec<i32> = spots.iter().map(|s| s.snr_db).collect();
+                Some(BandSignal {
+                    frequency: latest.freq.get::<kilohertz>(),
+                    callsign: call.to_string(),
+                    mode: latest.mode.clone(),
+                    segment: latest.segment,
+                    age_secs: (now - latest.timestamp).num_seconds(),
+                    snr_db: median(&mut snr),
+                    num_skimmers: skimmers.len(),
+                })
+            })
+            .collect();
+        signals.sort_by(|a, b| {
+            a.frequency
+                .total_cmp(&b.frequency)
+                .then_with(|| a.callsign.cmp(&b.callsign))
+        });
+
+        let occupied: Vec<(f64, f64)> = signals
+            .iter()
+            .map(|s| {
+                let half_width =
+                    Frequency::new::<hertz>(signal_bandwidth_hz(&s.mode) / 2.0).get::<kilohertz>();
+                (s.frequency - half_width, s.frequency + half_width)
+            })
+            .collect();
+        let min_width = min_gap.get::<kilohertz>();
+        let gaps = band
+            .segments
+            .iter()
+            .filter(|s| matches!(s.kind, SegmentKind::Cw | SegmentKind::Digi))
+            .flat_map(|segment| {
+                let lower = segment.lower.get::<kilohertz>();
+                let upper = segment.upper.get::<kilohertz>();
+                find_gaps(lower, upper, &occupied, min_width)
+                    .into_iter()
+                    .map(|(lower, upper)| Gap {
+                        segment: segment.kind,
+                        lower,
+                        upper,
+                        width_hz: Frequency::new::<kilohertz>(upper - lower).get::<hertz>(),
+                    })
+            })
+            .collect();
+
+        Some(BandMap {
+            band: band.name.clone(),
+            signals,
+            gaps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[], 1.0, vec![(7000.0, 7010.0)])]
+    #[case(&[(7002.0, 7002.0), (7005.0, 7005.0)], 2.0, vec![(7000.0, 7002.0), (7002.0, 7005.0), (7005.0, 7010.0)])]
+    #[case(&[(7002.0, 7002.0), (7005.0, 7005.0)], 3.0, vec![(7002.0, 7005.0), (7005.0, 7010.0)])]
+    #[case(&[(6990.0, 6990.0), (7001.0, 7001.0), (7009.0, 7009.0)], 5.0, vec![(7001.0, 7009.0)])]
+    #[case(&[(7001.85, 7002.15), (7005.85, 7006.15)], 3.0, vec![(7002.15, 7005.85), (7006.15, 7010.0)])]
+    #[case(&[(6999.85, 7000.15), (7002.0, 7004.0), (7003.0, 7003.5)], 5.0, vec![(7004.0, 7010.0)])]
+    fn gaps(
+        #[case] occupied: &[(f64, f64)],
+        #[case] min_width: f64,
+        #[case] expected: Vec<(f64, f64)>,
+    ) {
+        assert_eq!(find_gaps(7000.0, 7010.0, occupied, min_width), expected);
+    }
+
+    #[rstest]
+    #[case("CW", 300.0)]
+    #[case("FT8", 50.0)]
+    #[case("RTTY", 500.0)]
+    fn bandwidth(#[case] mode: &str, #[case] expected: f64) {
+        assert_eq!(signal_bandwidth_hz(mode), expected);
+    }
+}
@@ -0,0 +1,189 @@
+use super::{Spot, SpotDB};
+use crate::beacons::{NCDXF_BEACONS, NCDXF_FREQUENCIES_KHZ, ncdxf_transmitting};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uom::si::f64::Frequency;
+use uom::si::frequency::kilohertz;
+
+/// How a beacon was heard on one band.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BeaconReport {
+    pub callsign: String,
+    pub band: String,
+    pub frequency: f64, // kHz, most recent spot
+    pub num_skimmers: usize,
+    pub snr_db: i32, // most recent spot
+    pub max_snr_db: i32,
+    pub last_heard: DateTime<Utc>,
+}
+
+/// One of the 18 NCDXF beacons on one band, `None` fields if not heard.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NcdxfBeaconReport {
+    pub callsign: String,
+    pub location: String,
+    pub heard: bool,
+    pub num_skimmers: usize,
+    pub snr_db: Option<i32>,
+    pub max_snr_db: Option<i32>,
+    pub last_heard: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NcdxfBand {
+    pub band: String,
+    pub frequency: f64, // kHz
+    /// beacon transmitting right now
+    pub transmitting: String,
+    pub num_heard: usize,
+    /// in schedule order
+    pub beacons: Vec<NcdxfBeaconReport>,
+}
+
+/// Beacons heard by skimmers, the NCDXF network per band and all other beacons.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Beacons {
+    pub ncdxf: Vec<NcdxfBand>,
+    pub other: Vec<BeaconReport>,
+}
+
+impl BeaconReport {
+    fn from_spots(spots: &[&Spot]) -> Option<Self> {
+        let last = spots.iter().max_by_key(|s| s.timestamp)?;
+        let skimmers: HashSet<&str> = spots.iter().map(|s| s.spotter.as_str()).collect();
+        Some(Self {
+            callsign: last.spotted.clone(),
+            band: last.band.clone().unwrap_or_default(),
+            frequency: last.freq.get::<kilohertz>(),
+            num_skimmers: skimmers.len(),
+            snr_db: last.snr_db,
+            max_snr_db: spots.iter().map(|s| s.snr_db).max()?,
+            last_heard: last.timestamp,
+        })
+    }
+}
+
+impl SpotDB {
+    /// Beacons heard by skimmers in `region`, or by all skimmers.
+    /// `None` if the region does not exist.
+    pub fn get_beacons(&self, region: Option<&str>, now: DateTime<Utc>) -> Option<Beacons> {
+        let region = match region {
+            Some(name) => Some(self.get_region(name)?),
+            None => None,
+        };
+        let mut by_band_call: HashMap<(&str, &str), Vec<&Spot>> = HashMap::new();
+        for spot in self
+            .beacons
+            .iter()
+            .filter(|s| region.is_none_or(|r| r.match_callsign(&s.spotter)))
+        {
+            if let Some(band) = &spot.band {
+                by_band_call
+                    .entry((band, &spot.spotted))
+                    .or_default()
+                    .push(spot);
+            }
+        }
+
+        let ncdxf = NCDXF_FREQUENCIES_KHZ
+            .iter()
+            .enumerate()
+            .filter_map(|(i, khz)| {
+                let band = self
+                    .band_plan
+                    .find_band(Frequency::new::<kilohertz>(*khz))?;
+                let beacons: Vec<NcdxfBeaconReport> = NCDXF_BEACONS
+                    .iter()
+                    .map(|beacon| {
+                        let report = by_band_call
+                            .remove(&(band.name.as_str(), beacon.callsign))
+                            .and_then(|spots| BeaconReport::from_spots(&spots));
+                        NcdxfBeaconReport {
+                            callsign: beacon.callsign.to_string(),
+                            location: beacon.location.to_string(),
+                            heard: report.is_some(),
+                            num_skimmers: report.as_ref().map_or(0, |r| r.num_skimmers),
+                            snr_db: report.as_ref().map(|r| r.snr_db),
+                            max_snr_db: report.as_ref().map(|r| r.max_snr_db),
+                            last_heard: report.as_ref().map(|r| r.last_heard),
+                        }
+                    })
+                    .collect();
+                Some(NcdxfBand {
+                    band: band.name.clone(),
+                    frequency: *khz,
+                    transmitting: ncdxf_transmitting(i, now).callsign.to_string(),
+                    num_heard: beacons.iter().filter(|b| b.heard).count(),
+                    beacons,
+                })
+            })
+            .collect();
+
+        // NCDXF beacons heard off their schedule frequencies end up here too
+        let mut other: Vec<BeaconReport> = by_band_call
+            .values()
+            .filter_map(|spots| BeaconReport::from_spots(spots))
+            .collect();
+        other.sort_by(|a, b| {
+            b.last_heard
+                .cmp(&a.last_heard)
+                .then_with(|| a.callsign.cmp(&b.callsign))
+        });
+        Some(Beacons { ncdxf, other })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn beacons_kept_apart() {
+        let mut db = SpotDB::default();
+        db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        db.set_beacon_calls(["DK0WCY".to_string()]);
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        db.add_spot("DL1AAA", "4U1UN", khz(14100.0), "CW", 12, 22, "NCDXF", now);
+        db.add_spot("W1CCC", "4U1UN", khz(14100.1), "CW", 25, 22, "NCDXF", now);
+        db.add_spot("DL1AAA", "OH2B", khz(21150.0), "CW", 8, 22, "NCDXF", now);
+        db.add_spot("DL1AAA", "DK0WCY", khz(10144.0), "CW", 20, 22, "CQ", now);
+        db.add_spot(
+            "DL1AAA",
+            "DL0IGI/B",
+            khz(28205.0),
+            "CW",
+            5,
+            22,
+            "BEACON",
+            now,
+        );
+        db.add_spot("DL1AAA", "K1ABC", khz(14025.0), "CW", 10, 25, "CQ", now);
+
+        assert_eq!(db.beacon_spots().len(), 5);
+        assert_eq!(db.spots_in_db(), 1);
+
+        let beacons = db.get_beacons(None, now).unwrap();
+        let twenty = beacons.ncdxf.iter().find(|b| b.band == "20m").unwrap();
+        assert_eq!(twenty.num_heard, 1);
+        assert_eq!(twenty.beacons[0].num_skimmers, 2);
+        assert_eq!(twenty.beacons[0].max_snr_db, Some(25));
+        let fifteen = beacons.ncdxf.iter().find(|b| b.band == "15m").unwrap();
+        assert!(
+            fifteen
+                .beacons
+                .iter()
+                .any(|b| b.callsign == "OH2B" && b.heard)
+        );
+        let mut other: Vec<&str> = beacons.other.iter().map(|b| b.callsign.as_str()).collect();
+        other.sort_unstable();
+        assert_eq!(other, vec!["DK0WCY", "DL0IGI/B"]);
+
+        let europe = db.get_beacons(Some("europe"), now).unwrap();
+        let twenty = europe.ncdxf.iter().find(|b| b.band == "20m").unwrap();
+        assert_eq!(twenty.beacons[0].max_snr_db, Some(12));
+        assert!(db.get_beacons(Some("nowhere"), now).is_none());
+    }
+}
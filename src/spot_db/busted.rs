@@ -0,0 +1,166 @@
+use super::{Spot, SpotDB};
+use std::collections::HashSet;
+use std::sync::Arc;
+use uom::si::f64::Frequency;
+use uom::si::frequency::hertz;
+
+/// A call heard by at least this many skimmers is considered confirmed.
+const MIN_CONFIRMING_SKIMMERS: usize = 3;
+/// A busted decode is this close to the frequency of the real call.
+const BUSTED_TOLERANCE_HZ: f64 = 500.0;
+/// Busted decodes usually differ from the real call by a single character.
+const MAX_EDIT_DISTANCE: usize = 1;
+
+/// Levenshtein distance between two callsigns.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+impl SpotDB {
+    /// Calls heard by a single skimmer only, close in spelling and frequency
+    /// to a call confirmed by several skimmers. Kept up to date as spots
+    /// come and go.
+    pub fn busted_calls(&self) -> Arc<HashSet<String>> {
+        self.busted.clone()
+    }
+
+    /// Finds again whether the calls that adding or removing `changed`
+    /// spots can affect are busted: their own calls, similar calls close in
+    /// frequency and, if a call became confirmed or stopped being so, the
+    /// similar calls close to any of its spots.
+    pub(super) fn update_busted(&mut self, changed: &[&Spot]) {
+        let mut affected: HashSet<String> = HashSet::new();
+        for spot in changed {
+            affected.insert(spot.spotted.clone());
+            affected.extend(self.similar_calls_near(&spot.spotted, spot.freq));
+            let spotters: HashSet<&str> = self
+                .spots
+                .by_call(&spot.spotted)
+                .map(|s| s.spotter.as_str())
+                .collect();
+            let confirmed = spotters.len() >= MIN_CONFIRMING_SKIMMERS;
+            if confirmed == self.confirmed.contains(&spot.spotted) {
+                continue;
+            }
+            if confirmed {
+                self.confirmed.insert(spot.spotted.clone());
+            } else {
+                self.confirmed.remove(&spot.spotted);
+            }
+            let freqs: Vec<Frequency> = self.spots.by_call(&spot.spotted).map(|s| s.freq).collect();
+            for freq in freqs {
+                affected.extend(self.similar_calls_near(&spot.spotted, freq));
+            }
+        }
+        for call in affected {
+            let busted = self.is_busted(&call);
+            if busted != self.busted.contains(&call) {
+                let calls = Arc::make_mut(&mut self.busted);
+                if busted {
+                    calls.insert(call);
+                } else {
+                    calls.remove(&call);
+                }
+            }
+        }
+    }
+
+    /// Other calls spotted within `BUSTED_TOLERANCE_HZ` of `freq` that
+    /// differ from `call` by at most `MAX_EDIT_DISTANCE` characters.
+    fn similar_calls_near(&self, call: &str, freq: Frequency) -> HashSet<String> {
+        let tolerance = Frequency::new::<hertz>(BUSTED_TOLERANCE_HZ);
+        self.spots
+            .in_range(freq - tolerance, freq + tolerance)
+            .map(|s| &s.spotted)
+            .filter(|other| *other != call && edit_distance(call, other) <= MAX_EDIT_DISTANCE)
+            .cloned()
+            .collect()
+    }
+
+    fn is_busted(&self, call: &str) -> bool {
+        let spots: Vec<&Arc<Spot>> = self.spots.by_call(call).collect();
+        let Some(first) = spots.first() else {
+            return false;
+        };
+        spots.iter().all(|s| s.spotter == first.spotter)
+            && spots.iter().any(|spot| {
+                self.similar_calls_near(call, spot.freq)
+                    .iter()
+                    .any(|other| self.confirmed.contains(other))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    #[case("DL1ABC", "DL1ABC", 0)]
+    #[case("DL1ABC", "DL1ABD", 1)]
+    #[case("DL1ABC", "DL1AB", 1)]
+    #[case("DL1ABC", "DL1XBC", 1)]
+    #[case("DL1ABC", "DL2ABCD", 2)]
+    #[case("K1ABC", "F5XYZ", 5)]
+    fn distance(#[case] a: &str, #[case] b: &str, #[case] expected: usize) {
+        assert_eq!(edit_distance(a, b), expected);
+    }
+
+    #[rstest]
+    fn flag_busted_calls() {
+        let mut db = SpotDB::default();
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        for spotter in ["DL1AAA", "F1BBB", "G1CCC"] {
+            db.add_spot(spotter, "K1ABC", khz(14025.0), "CW", 20, 25, "CQ", now);
+        }
+        // single skimmer, one character off and on the same frequency
+        db.add_spot("OH1DDD", "K1ABD", khz(14025.1), "CW", 3, 25, "CQ", now);
+        // single skimmer, but far away in frequency
+        db.add_spot("OH1DDD", "K1ABE", khz(14040.0), "CW", 3, 25, "CQ", now);
+        // close to a call that is not confirmed
+        db.add_spot("OH1DDD", "W1XYZ", khz(14030.0), "CW", 3, 25, "CQ", now);
+        db.add_spot("DL1AAA", "W1XYQ", khz(14030.0), "CW", 3, 25, "CQ", now);
+
+        let busted = db.busted_calls();
+        assert_eq!(*busted, HashSet::from(["K1ABD".to_string()]));
+    }
+
+    #[rstest]
+    fn busted_calls_follow_expiry() {
+        let mut db = SpotDB::default();
+        let now = Utc::now();
+        let earlier = now - chrono::Duration::minutes(10);
+        let khz = Frequency::new::<kilohertz>;
+        db.add_spot("OH1DDD", "K1ABD", khz(14025.1), "CW", 3, 25, "CQ", now);
+        db.add_spot("DL1AAA", "K1ABC", khz(14025.0), "CW", 20, 25, "CQ", earlier);
+        db.add_spot("F1BBB", "K1ABC", khz(14025.0), "CW", 20, 25, "CQ", earlier);
+        assert!(db.busted_calls().is_empty());
+        // a third skimmer confirms K1ABC, which busts K1ABD
+        db.add_spot("G1CCC", "K1ABC", khz(14024.9), "CW", 20, 25, "CQ", earlier);
+        assert_eq!(*db.busted_calls(), HashSet::from(["K1ABD".to_string()]));
+        // once the confirming spots are gone, K1ABD stands on its own
+        db.remove_spots_before(now);
+        assert!(db.busted_calls().is_empty());
+        // a second skimmer hearing K1ABD keeps it from being busted
+        db.add_spot("DL1AAA", "K1ABD", khz(14025.1), "CW", 3, 25, "CQ", now);
+        for spotter in ["DL1AAA", "F1BBB", "G1CCC"] {
+            db.add_spot(spotter, "K1ABC", khz(14025.0), "CW", 20, 25, "CQ", now);
+        }
+        assert!(db.busted_calls().is_empty());
+    }
+}
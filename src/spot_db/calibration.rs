@@ -0,0 +1,132 @@
+use super::{Spot, median};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uom::si::f64::Frequency;
+use uom::si::frequency::hertz;
+
+/// Minimum number of skimmers hearing a station in the same minute before
+/// their spots are compared.
+const MIN_SKIMMERS: usize = 3;
+/// Minimum number of comparisons before an offset is reported for a skimmer.
+const MIN_SAMPLES: usize = 5;
+/// Deviations larger than this are assumed to be a different signal.
+pub(super) const MAX_DEVIATION_PPM: f64 = 50.0;
+
+/// Estimated frequency offset of a skimmer relative to its peers.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// positive if the skimmer reports frequencies too high
+    pub ppm: f64,
+    pub samples: usize,
+}
+
+impl Calibration {
+    /// Removes the estimated offset from a frequency reported by the skimmer.
+    pub fn correct(&self, freq: Frequency) -> Frequency {
+        freq / (1.0 + self.ppm * 1e-6)
+    }
+}
+
+/// Estimates a per-skimmer offset by comparing spots of the same station on
+/// the same band within the same minute. The median of all skimmers in such a
+/// group is taken as reference, the offset of a skimmer is the median of its
+/// deviations from the reference.
+pub fn estimate<'a>(spots: impl Iterator<Item = &'a Arc<Spot>>) -> HashMap<String, Calibration> {
+    // spotted, band, minute since the epoch
+    let mut groups: HashMap<(&str, &str, i64), Vec<&Spot>> = HashMap::new();
+    for spot in spots {
+        if let Some(band) = &spot.band {
+            let minute = spot.timestamp.timestamp().div_euclid(60);
+            groups
+                .entry((&spot.spotted, band, minute))
+                .or_default()
+                .push(spot);
+        }
+    }
+
+    let mut deviations: HashMap<&str, Vec<f64>> = HashMap::new();
+    for group in groups.values() {
+        let mut skimmers: Vec<&str> = group.iter().map(|s| s.spotter.as_str()).collect();
+        skimmers.sort_unstable();
+        skimmers.dedup();
+        if skimmers.len() < MIN_SKIMMERS {
+            continue;
+        }
+        let mut freqs: Vec<f64> = group.iter().map(|s| s.freq.get::<hertz>()).collect();
+        let Some(reference) = median(&mut freqs) else {
+            continue;
+        };
+        for spot in group {
+            let ppm = (spot.freq.get::<hertz>() - reference) / reference * 1e6;
+            if ppm.abs() <= MAX_DEVIATION_PPM {
+                deviations.entry(&spot.spotter).or_default().push(ppm);
+            }
+        }
+    }
+
+    deviations
+        .into_iter()
+        .filter(|(_, d)| d.len() >= MIN_SAMPLES)
+        .filter_map(|(spotter, mut d)| {
+            let samples = d.len();
+            median(&mut d).map(|ppm| (spotter.to_string(), Calibration { ppm, samples }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spot_db::SpotDB;
+    use chrono::Utc;
+    use rstest::rstest;
+    use std::time::Duration;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    fn estimate_offset_skimmer() {
+        let mut db = SpotDB::default();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        // 10 ppm high
+        let f_off = Frequency::new::<hertz>(14_025_140.25);
+        let now = Utc::now();
+        for minute in 0..6 {
+            let t = now - Duration::from_secs(minute * 60);
+            for spotter in ["DL1AAA", "F1BBB", "G1CCC"] {
+                db.add_spot(spotter, "K1ABC", f, "CW", 10, 25, "CQ", t);
+            }
+            db.add_spot("HB9OFF", "K1ABC", f_off, "CW", 10, 25, "CQ", t);
+        }
+        let calibration = estimate(db.spots.iter());
+        let off = calibration.get("HB9OFF").expect("no estimate");
+        assert_eq!(off.samples, 6);
+        assert!((off.ppm - 10.0).abs() < 0.01);
+        assert!(calibration["DL1AAA"].ppm.abs() < 0.01);
+        let corrected = off.correct(f_off).get::<hertz>();
+        assert!((corrected - 14_025_000.0).abs() < 1.0);
+    }
+
+    #[rstest]
+    fn estimate_within_the_minute() {
+        let mut db = SpotDB::default();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        let f_off = Frequency::new::<hertz>(14_025_140.25);
+        let now = Utc::now();
+        let minute_start = now - Duration::from_secs(now.timestamp().rem_euclid(60) as u64);
+        for minute in 1..6 {
+            let t = minute_start - Duration::from_secs(minute * 60);
+            // the skimmers report at different seconds of the minute
+            for (second, spotter) in [(5, "DL1AAA"), (20, "F1BBB"), (40, "G1CCC")] {
+                let at = t + Duration::from_secs(second);
+                db.add_spot(spotter, "K1ABC", f, "CW", 10, 25, "CQ", at);
+            }
+            let at = t + Duration::from_secs(55);
+            db.add_spot("HB9OFF", "K1ABC", f_off, "CW", 10, 25, "CQ", at);
+        }
+        let calibration = estimate(db.spots.iter());
+        let off = calibration.get("HB9OFF").expect("no estimate");
+        assert_eq!(off.samples, 5);
+        assert!((off.ppm - 10.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,423 @@
+use super::aggregate::Counter;
+use super::{Locator, Region, Spot, SpotDB, StationLocation, window_start};
+use crate::bands::BandPlan;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Which multipliers count.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ContestProfile {
+    /// CQ WW: CQ zones and DXCC entities per band
+    CqWw,
+    /// CQ WPX: prefixes, counted once per band here
+    Wpx,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiplierKind {
+    Zone,
+    Entity,
+    Prefix,
+}
+
+/// A multiplier available on a band, from all current spots.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Multiplier {
+    pub kind: MultiplierKind,
+    pub name: String,
+    /// stations giving the multiplier, sorted
+    pub calls: Vec<String>,
+    /// heard by a skimmer of the region
+    pub heard_in_region: bool,
+    pub first_heard: DateTime<Utc>,
+    /// first heard within the rate window
+    pub new: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ContestBand {
+    pub band: String,
+    pub num_multipliers: usize,
+    pub num_region_multipliers: usize,
+    /// distinct calls spotted within the rate window, scaled to an hour
+    pub calls_per_hour: u32,
+    pub region_calls_per_hour: u32,
+    pub multipliers: Vec<Multiplier>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Contest {
+    pub profile: ContestProfile,
+    pub region: String,
+    pub rate_window_mins: u64,
+    pub bands: Vec<ContestBand>,
+}
+
+impl ContestProfile {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cqww" => Some(Self::CqWw),
+            "wpx" => Some(Self::Wpx),
+            _ => None,
+        }
+    }
+
+    fn multipliers(self, call: &str, location: &StationLocation) -> Vec<(MultiplierKind, String)> {
+        match self {
+            Self::CqWw => {
+                let zone = location.cq_zone.as_ref().map(|z| {
+                    (
+                        MultiplierKind::Zone,
+                        z.trim_start_matches("CQ_").to_string(),
+                    )
+                });
+                let entity = location
+                    .entity
+                    .as_ref()
+                    .map(|e| (MultiplierKind::Entity, e.clone()));
+                zone.into_iter().chain(entity).collect()
+            }
+            Self::Wpx => wpx_prefix(call)
+                .map(|p| (MultiplierKind::Prefix, p))
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Suffixes that don't change the prefix of a call.
+const IGNORED_SUFFIXES: [&str; 6] = ["P", "M", "MM", "AM", "QRP", "B"];
+
+/// The WPX prefix of `call`: everything up to the last digit of the call,
+/// e.g. "JA1" for JA1XYZ. A portable designator replaces it, a prefix
+/// without digit gets a 0 ("DL0" for DL/K1ABC) and a single digit replaces
+/// the call area ("K4" for K1ABC/4).
+pub fn wpx_prefix(call: &str) -> Option<String> {
+    let parts: Vec<&str> = call
+        .split('/')
+        .filter(|p| !p.is_empty() && !IGNORED_SUFFIXES.contains(p))
+        .collect();
+    let base = parts.iter().max_by_key(|p| p.len())?;
+    let prefix = |p: &str| match p.rfind(|c: char| c.is_ascii_digit()) {
+        Some(i) => p[..=i].to_string(),
+        None => format!("{}0", &p[..p.len().min(2)]),
+    };
+    let designator = parts.iter().find(|p| !std::ptr::eq(*p, base));
+    match designator {
+        Some(d) if d.len() == 1 && d.chars().all(|c| c.is_ascii_digit()) => {
+            let base = prefix(base);
+            Some(format!(
+                "{}{d}",
+                base.trim_end_matches(|c: char| c.is_ascii_digit())
+            ))
+        }
+        Some(d) => Some(prefix(d)),
+        None => Some(prefix(base)),
+    }
+}
+
+/// Spot times of every call per band, over all skimmers. Behind its own lock
+/// like a region, so contest views are computed without the database lock.
+#[derive(Debug, Default)]
+pub(super) struct BandCalls(HashMap<String, HashMap<String, Counter<DateTime<Utc>>>>);
+
+impl BandCalls {
+    pub(super) fn add(&mut self, spot: &Spot) {
+        if let Some(band) = &spot.band {
+            self.0
+                .entry(band.clone())
+                .or_default()
+                .entry(spot.spotted.clone())
+                .or_default()
+                .add(spot.timestamp);
+        }
+    }
+
+    pub(super) fn remove(&mut self, spot: &Spot) {
+        if let Some(band) = &spot.band
+            && let Some(calls) = self.0.get_mut(band)
+            && let Some(times) = calls.get_mut(&spot.spotted)
+        {
+            times.remove(&spot.timestamp);
+            if times.is_empty() {
+                calls.remove(&spot.spotted);
+            }
+        }
+    }
+
+    /// Estimated heap usage in bytes.
+    pub(super) fn memory_bytes(&self) -> usize {
+        self.0
+            .values()
+            .flat_map(HashMap::iter)
+            .map(|(call, times)| size_of::<String>() + call.capacity() + times.memory_bytes())
+            .sum()
+    }
+}
+
+// multiplier -> calls, heard in region, first heard
+type MultiplierCalls<'a> =
+    HashMap<(MultiplierKind, String), (BTreeSet<&'a str>, bool, DateTime<Utc>)>;
+
+fn per_hour(calls: usize, window: Duration) -> u32 {
+    (calls as f64 * 3600.0 / window.as_secs_f64().max(1.0)).round() as u32
+}
+
+/// What a contest view is computed from, taken under the database lock so
+/// the view can be computed without holding it.
+pub struct ContestSnapshot {
+    profile: ContestProfile,
+    region: Arc<Region>,
+    rate_window: Duration,
+    now: DateTime<Utc>,
+    band_calls: Arc<RwLock<BandCalls>>,
+    busted: Arc<HashSet<String>>,
+    locator: Locator,
+    band_plan: Arc<BandPlan>,
+}
+
+impl SpotDB {
+    /// Multipliers of `profile` available per band from all current spots,
+    /// those heard by skimmers of `region` highlighted, and the calls per
+    /// hour over the last `rate_window`. Probably busted calls are left out.
+    /// `None` if the region does not exist.
+    pub fn get_contest(
+        &self,
+        profile: ContestProfile,
+        region: &str,
+        rate_window: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<Contest> {
+        Some(
+            self.contest_snapshot(profile, region, rate_window, now)?
+                .contest(),
+        )
+    }
+
+    /// What `get_contest` computes the contest view from.
+    pub fn contest_snapshot(
+        &self,
+        profile: ContestProfile,
+        region: &str,
+        rate_window: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<ContestSnapshot> {
+        Some(ContestSnapshot {
+            profile,
+            region: self.get_region(region)?.clone(),
+            rate_window,
+            now,
+            band_calls: self.band_calls.clone(),
+            busted: self.busted_calls(),
+            locator: self.locator(),
+            band_plan: self.band_plan.clone(),
+        })
+    }
+}
+
+impl ContestSnapshot {
+    /// See `SpotDB::get_contest`. Walks the calls per band of all skimmers
+    /// and of the region, not the spots.
+    pub fn contest(self) -> Contest {
+        let Self {
+            profile,
+            region,
+            rate_window,
+            now,
+            band_calls,
+            busted,
+            locator,
+            band_plan,
+        } = self;
+        let rate_start = window_start(now, rate_window);
+        let region_spots = region.spots.read().expect("RwLock poisoned");
+        let band_calls = band_calls.read().expect("RwLock poisoned");
+        let mut multipliers: HashMap<&str, Vec<(MultiplierKind, String)>> = HashMap::new();
+        let bands = band_plan
+            .bands()
+            .iter()
+            .filter_map(|band| {
+                let calls = band_calls.0.get(&band.name).filter(|c| !c.is_empty())?;
+                let region_newest = region_spots.aggregate.newest_on_band(&band.name);
+                let mut band_multipliers = MultiplierCalls::new();
+                let mut num_calls = 0;
+                let mut num_region_calls = 0;
+                for (call, times) in calls {
+                    if busted.contains(call) {
+                        continue;
+                    }
+                    let (Some(first_heard), Some(last_heard)) =
+                        (times.first(), times.iter_rev().next())
+                    else {
+                        continue;
+                    };
+                    let region_last_heard = region_newest.get(call.as_str());
+                    num_calls += usize::from(*last_heard >= rate_start);
+                    num_region_calls +=
+                        usize::from(region_last_heard.is_some_and(|t| *t >= rate_start));
+                    let call_multipliers = multipliers
+                        .entry(call)
+                        .or_insert_with(|| profile.multipliers(call, &locator.locate(call)));
+                    for multiplier in call_multipliers.iter() {
+                        let (calls, heard_in_region, first) = band_multipliers
+                            .entry(multiplier.clone())
+                            .or_insert_with(|| (BTreeSet::new(), false, *first_heard));
+                        calls.insert(call);
+                        *heard_in_region |= region_last_heard.is_some();
+                        *first = (*first).min(*first_heard);
+                    }
+                }
+                let mut multipliers: Vec<Multiplier> = band_multipliers
+                    .into_iter()
+                    .map(
+                        |((kind, name), (calls, heard_in_region, first_heard))| Multiplier {
+                            kind,
+                            name,
+                            calls: calls.into_iter().map(ToString::to_string).collect(),
+                            heard_in_region,
+                            first_heard,
+                            new: first_heard >= rate_start,
+                        },
+                    )
+                    .collect();
+                multipliers.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+                Some(ContestBand {
+                    band: band.name.clone(),
+                    num_multipliers: multipliers.len(),
+                    num_region_multipliers: multipliers
+                        .iter()
+                        .filter(|m| m.heard_in_region)
+                        .count(),
+                    calls_per_hour: per_hour(num_calls, rate_window),
+                    region_calls_per_hour: per_hour(num_region_calls, rate_window),
+                    multipliers,
+                })
+            })
+            .collect();
+        Contest {
+            profile,
+            region: region.name.clone(),
+            rate_window_mins: rate_window.as_secs() / 60,
+            bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    #[case("K1ABC", Some("K1"))]
+    #[case("JA1XYZ", Some("JA1"))]
+    #[case("9A2AA", Some("9A2"))]
+    #[case("S51A", Some("S51"))]
+    #[case("K1ABC/P", Some("K1"))]
+    #[case("K1ABC/4", Some("K4"))]
+    #[case("DL/K1ABC", Some("DL0"))]
+    #[case("VP2V/K1ABC", Some("VP2"))]
+    #[case("RAEM", Some("RA0"))]
+    #[case("", None)]
+    fn wpx(#[case] call: &str, #[case] expected: Option<&str>) {
+        assert_eq!(wpx_prefix(call).as_deref(), expected);
+    }
+
+    #[rstest]
+    fn contest_multipliers() {
+        let mut db = SpotDB::default();
+        let prefixes = |p: &[&str]| p.iter().map(ToString::to_string).collect();
+        db.add_region("europe".to_string(), prefixes(&["DL"]));
+        db.add_region("united_states".to_string(), prefixes(&["K", "W"]));
+        db.add_region("CQ_5".to_string(), prefixes(&["K1", "W1"]));
+        db.add_region("CQ_4".to_string(), prefixes(&["K0", "W0"]));
+        let now = Utc::now();
+        let earlier = now - Duration::from_secs(20 * 60);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", earlier);
+        db.add_spot("DL1AAA", "W1XYZ", f, "CW", 10, 25, "CQ", now);
+        db.add_spot("W3LPL", "K0ABC", f, "CW", 10, 25, "CQ", now);
+
+        let window = Duration::from_secs(10 * 60);
+        let contest = db
+            .get_contest(ContestProfile::CqWw, "europe", window, now)
+            .unwrap();
+        let twenty = &contest.bands[0];
+        let multipliers: Vec<(MultiplierKind, &str, bool, bool)> = twenty
+            .multipliers
+            .iter()
+            .map(|m| (m.kind, m.name.as_str(), m.heard_in_region, m.new))
+            .collect();
+        assert_eq!(
+            multipliers,
+            vec![
+                (MultiplierKind::Zone, "4", false, true),
+                (MultiplierKind::Zone, "5", true, false),
+                (MultiplierKind::Entity, "united_states", true, false),
+            ]
+        );
+        assert_eq!(twenty.num_region_multipliers, 2);
+        // two calls in 10 minutes
+        assert_eq!(twenty.calls_per_hour, 12);
+        assert_eq!(twenty.region_calls_per_hour, 6);
+
+        let wpx = db
+            .get_contest(ContestProfile::Wpx, "europe", window, now)
+            .unwrap();
+        let prefixes: Vec<&str> = wpx.bands[0]
+            .multipliers
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+        assert_eq!(prefixes, vec!["K0", "K1", "W1"]);
+        assert!(
+            db.get_contest(ContestProfile::Wpx, "nowhere", window, now)
+                .is_none()
+        );
+        // a rate window beyond all time counts every spot
+        let forever = Duration::from_secs(u64::MAX);
+        let contest = db
+            .get_contest(ContestProfile::CqWw, "europe", forever, now)
+            .unwrap();
+        assert_eq!(contest.bands[0].calls_per_hour, 0);
+    }
+
+    #[rstest]
+    fn contest_zones_of_shipped_regions() {
+        let mut db = SpotDB::default();
+        db.add_dxcc_regions(&crate::region_loader::load("data/dxcc.json").unwrap());
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("W3LPL", "DL1ABC", f, "CW", 10, 25, "CQ", now);
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", now);
+        db.add_spot("DL1AAA", "VE3ABC", f, "CW", 10, 25, "CQ", now);
+
+        let window = Duration::from_secs(10 * 60);
+        let contest = db
+            .get_contest(ContestProfile::CqWw, "EU", window, now)
+            .unwrap();
+        let multipliers: Vec<(MultiplierKind, &str)> = contest.bands[0]
+            .multipliers
+            .iter()
+            .map(|m| (m.kind, m.name.as_str()))
+            .collect();
+        // the zones of the US and Canada depend on the call area, which the
+        // regions don't know
+        assert_eq!(
+            multipliers,
+            vec![
+                (MultiplierKind::Zone, "14"),
+                (MultiplierKind::Entity, "canada"),
+                (MultiplierKind::Entity, "germany"),
+                (MultiplierKind::Entity, "united_states_of_america"),
+            ]
+        );
+    }
+}
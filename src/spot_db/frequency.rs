@@ -0,0 +1,151 @@
+use super::calibration::MAX_DEVIATION_PPM;
+use super::{Spot, SpotDB, window_start};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use uom::si::f64::Frequency;
+use uom::si::frequency::{hertz, kilohertz};
+
+/// Restricts which spots are considered in a frequency lookup.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyFilter {
+    /// only spots in one of these modes, all modes if empty
+    pub modes: Vec<String>,
+    /// only spots younger than this
+    pub max_age: Option<Duration>,
+    /// correct spot frequencies by the estimated skimmer offset
+    pub calibrated: bool,
+}
+
+impl FrequencyFilter {
+    fn matches(&self, spot: &Spot, now: DateTime<Utc>) -> bool {
+        let mode_ok = self.modes.is_empty() || self.modes.iter().any(|m| m == &spot.mode);
+        let age_ok = self
+            .max_age
+            .is_none_or(|a| window_start(now, a) <= spot.timestamp);
+        mode_ok && age_ok
+    }
+}
+
+/// A station heard within the requested frequency range.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FrequencyUser {
+    pub callsign: String,
+    pub frequencies: Vec<f64>, // kHz, as reported (or corrected)
+    pub offset_hz: f64,        // distance of the closest frequency to the requested one
+    pub modes: Vec<String>,
+    pub num_skimmers: usize,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl SpotDB {
+    /// Frequency of a spot, corrected by the skimmer's offset if `calibrated`.
+    fn spot_freq(&self, spot: &Spot, calibrated: bool) -> Frequency {
+        match self.calibration.get(&spot.spotter) {
+            Some(c) if calibrated => c.correct(spot.freq),
+            _ => spot.freq,
+        }
+    }
+
+    /// Finds all stations spotted between `lower` and `upper`, sorted by
+    /// distance from the middle of the range.
+    pub fn get_frequency_users(
+        &self,
+        lower: Frequency,
+        upper: Frequency,
+        filter: &FrequencyFilter,
+    ) -> Vec<FrequencyUser> {
+        let center = (lower + upper) / 2.0;
+        let now = Utc::now();
+        // corrected frequencies can be outside the requested range
+        let margin = if filter.calibrated {
+            upper * MAX_DEVIATION_PPM * 1e-6
+        } else {
+            Frequency::new::<hertz>(0.0)
+        };
+        let mut users: HashMap<&str, (FrequencyUser, Vec<&str>)> = HashMap::new();
+        for spot in self
+            .spots
+            .in_range(lower - margin, upper + margin)
+            .filter(|s| filter.matches(s, now))
+        {
+            let f = self.spot_freq(spot, filter.calibrated);
+            if f < lower || upper < f {
+                continue;
+            }
+            let offset_hz = (f - center).abs().get::<hertz>();
+            let (user, skimmers) = users.entry(&spot.spotted).or_insert_with(|| {
+                let user = FrequencyUser {
+                    callsign: spot.spotted.clone(),
+                    frequencies: Vec::new(),
+                    offset_hz,
+                    modes: Vec::new(),
+                    num_skimmers: 0,
+                    last_seen: spot.timestamp,
+                };
+                (user, Vec::new())
+            });
+            user.frequencies.push(f.get::<kilohertz>());
+            user.offset_hz = user.offset_hz.min(offset_hz);
+            user.modes.push(spot.mode.clone());
+            user.last_seen = user.last_seen.max(spot.timestamp);
+            skimmers.push(&spot.spotter);
+        }
+        let mut users: Vec<FrequencyUser> = users
+            .into_values()
+            .map(|(mut user, mut skimmers)| {
+                user.frequencies.sort_by(f64::total_cmp);
+                user.frequencies.dedup();
+                user.modes.sort_unstable();
+                user.modes.dedup();
+                skimmers.sort_unstable();
+                skimmers.dedup();
+                user.num_skimmers = skimmers.len();
+                user
+            })
+            .collect();
+        users.sort_by(|a, b| {
+            a.offset_hz
+                .total_cmp(&b.offset_hz)
+                .then_with(|| a.callsign.cmp(&b.callsign))
+        });
+        users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn frequency_users_sorted_and_filtered() {
+        let mut db = SpotDB::default();
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        db.add_spot("DL1AAA", "FAR", khz(14025.15), "CW", 10, 25, "CQ", now);
+        db.add_spot("DL1AAA", "NEAR", khz(14025.05), "CW", 10, 25, "CQ", now);
+        db.add_spot("F1BBB", "NEAR", khz(14025.0), "CW", 10, 25, "CQ", now);
+        db.add_spot("F1BBB", "RTTY", khz(14025.0), "RTTY", 10, 45, "CQ", now);
+        db.add_spot("F1BBB", "OUT", khz(14025.5), "CW", 10, 25, "CQ", now);
+
+        let filter = FrequencyFilter {
+            modes: vec!["CW".to_string()],
+            ..Default::default()
+        };
+        let users = db.get_frequency_users(khz(14024.8), khz(14025.2), &filter);
+        let calls: Vec<&str> = users.iter().map(|u| u.callsign.as_str()).collect();
+        assert_eq!(calls, vec!["NEAR", "FAR"]);
+        assert_eq!(users[0].num_skimmers, 2);
+        assert_eq!(users[0].frequencies, vec![14025.0, 14025.05]);
+
+        // an age beyond all time keeps every spot
+        let filter = FrequencyFilter {
+            max_age: Some(Duration::from_secs(u64::MAX)),
+            ..Default::default()
+        };
+        let users = db.get_frequency_users(khz(14024.8), khz(14025.2), &filter);
+        assert_eq!(users.len(), 3);
+    }
+}
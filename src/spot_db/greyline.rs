@@ -0,0 +1,159 @@
+use super::{Spot, SpotDB, window_start};
+use crate::geo::LatLon;
+use crate::solar::{SolarState, subsolar_point, terminator};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longitude step between two points of the terminator.
+const TERMINATOR_STEP_DEG: f64 = 2.0;
+
+/// A skimmer to station path whose newest spot had both ends on the grey line.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GreylinePath {
+    pub spotter: String,
+    pub spotted: String,
+    pub band: String,
+    pub distance_km: Option<u32>,
+    pub bearing_deg: Option<u16>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// The day/night terminator and the paths currently heard along it.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Greyline {
+    pub time: DateTime<Utc>,
+    pub subsolar_point: LatLon,
+    pub terminator: Vec<LatLon>,
+    /// paths with spots since `now - max_age`, with both ends located
+    pub num_active_paths: usize,
+    /// longest first
+    pub paths: Vec<GreylinePath>,
+}
+
+impl SpotDB {
+    /// Terminator at `now` and the grey-line paths among the spots of the
+    /// last `max_age`, heard by skimmers in `region` or by all skimmers.
+    /// `None` if the region does not exist.
+    pub fn get_greyline(
+        &self,
+        region: Option<&str>,
+        max_age: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<Greyline> {
+        let spots: Vec<&Arc<Spot>> = match region {
+            Some(name) => self.region_spots(self.get_region(name)?),
+            None => self.spots.iter().collect(),
+        };
+        let cutoff = window_start(now, max_age);
+        // spotter, spotted, band -> newest spot
+        let mut newest: HashMap<(&str, &str, &str), &Spot> = HashMap::new();
+        for spot in spots {
+            let Some(band) = &spot.band else {
+                continue;
+            };
+            if spot.timestamp < cutoff
+                || spot.spotter_solar.is_none()
+                || spot.spotted_solar.is_none()
+            {
+                continue;
+            }
+            newest
+                .entry((&spot.spotter, &spot.spotted, band))
+                .and_modify(|s| {
+                    if spot.timestamp > s.timestamp {
+                        *s = spot;
+                    }
+                })
+                .or_insert(spot);
+        }
+        let mut paths: Vec<GreylinePath> = newest
+            .iter()
+            .filter(|(_, spot)| {
+                spot.spotter_solar == Some(SolarState::Greyline)
+                    && spot.spotted_solar == Some(SolarState::Greyline)
+            })
+            .map(|((spotter, spotted, band), spot)| GreylinePath {
+                spotter: spotter.to_string(),
+                spotted: spotted.to_string(),
+                band: band.to_string(),
+                distance_km: spot.path.map(|p| p.distance_km),
+                bearing_deg: spot.path.map(|p| p.bearing_deg),
+                last_seen: spot.timestamp,
+            })
+            .collect();
+        paths.sort_by(|a, b| {
+            b.distance_km
+                .cmp(&a.distance_km)
+                .then_with(|| a.spotter.cmp(&b.spotter))
+                .then_with(|| a.spotted.cmp(&b.spotted))
+                .then_with(|| a.band.cmp(&b.band))
+        });
+        Some(Greyline {
+            time: now,
+            subsolar_point: subsolar_point(now),
+            terminator: terminator(now, TERMINATOR_STEP_DEG),
+            num_active_paths: newest.len(),
+            paths,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::Locations;
+    use chrono::TimeZone;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    fn greyline_paths() {
+        let mut db = SpotDB::default();
+        let mut locations = Locations::default();
+        // around 19:30 UTC in June the sun sets in Berlin and rises in Tokyo,
+        // while it is night in Sydney
+        locations
+            .add_skimmers("DL1AAA 52.5 13.4\nJA1AAA 35.7 139.7\nVK2AAA -33.9 151.2")
+            .unwrap();
+        locations
+            .add_country_file(
+                "Fed. Rep. of Germany: 14: 28: EU: 51.0: -10.0: -1.0: DL:\n    DL;\n\
+                 Japan: 25: 45: AS: 35.7: -139.7: -9.0: JA:\n    JA;\n",
+            )
+            .unwrap();
+        db.set_locations(locations);
+        db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        let now = Utc.with_ymd_and_hms(2024, 6, 20, 19, 30, 0).unwrap();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("DL1AAA", "JA1ABC", f, "CW", 10, 25, "CQ", now);
+        db.add_spot("JA1AAA", "DL2ABC", f, "CW", 10, 25, "CQ", now);
+        db.add_spot("VK2AAA", "JA1ABC", f, "CW", 10, 25, "CQ", now);
+        // unknown location
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", now);
+        // too old
+        let old = now - Duration::from_secs(3600);
+        db.add_spot("DL1AAA", "JA2ABC", f, "CW", 10, 25, "CQ", old);
+
+        let window = Duration::from_secs(15 * 60);
+        let greyline = db.get_greyline(None, window, now).unwrap();
+        assert_eq!(greyline.terminator.len(), 181);
+        assert_eq!(greyline.num_active_paths, 3);
+        let mut paths: Vec<(&str, &str)> = greyline
+            .paths
+            .iter()
+            .map(|p| (p.spotter.as_str(), p.spotted.as_str()))
+            .collect();
+        paths.sort_unstable();
+        assert_eq!(paths, vec![("DL1AAA", "JA1ABC"), ("JA1AAA", "DL2ABC")]);
+
+        let europe = db.get_greyline(Some("europe"), window, now).unwrap();
+        assert_eq!(europe.paths.len(), 1);
+        assert!(db.get_greyline(Some("nowhere"), window, now).is_none());
+        let forever = Duration::from_secs(u64::MAX);
+        assert!(db.get_greyline(None, forever, now).is_some());
+    }
+}
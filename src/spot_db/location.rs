@@ -0,0 +1,191 @@
+use super::{Region, SpotDB};
+use crate::geo::Locations;
+use crate::region_loader::Dxcc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// What a region stands for, from the names given by the region loader:
+/// "CQ_14" for CQ zones, upper case two letter continent codes, and the
+/// lower case DXCC entity names for everything else.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionKind {
+    Entity,
+    CqZone,
+    Continent,
+}
+
+/// Where a station is, from the longest matching prefix of every region kind.
+/// A kind is unknown if regions of it tie for the longest prefix, e.g. the
+/// CQ zones of an entity spanning several zones.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct StationLocation {
+    pub entity: Option<String>,
+    pub continent: Option<String>,
+    pub cq_zone: Option<String>,
+}
+
+impl Region {
+    pub fn kind(&self) -> RegionKind {
+        if self.name.starts_with("CQ_") {
+            RegionKind::CqZone
+        } else if self.name.len() == 2 && self.name.chars().all(|c| c.is_ascii_uppercase()) {
+            RegionKind::Continent
+        } else {
+            RegionKind::Entity
+        }
+    }
+
+    /// Length of the longest prefix matching `callsign`.
+    fn match_len(&self, callsign: &str) -> Option<usize> {
+        self.prefixes
+            .iter()
+            .filter(|p| callsign.starts_with(p.as_str()))
+            .map(String::len)
+            .max()
+    }
+}
+
+impl SpotDB {
+    /// Adds a region per DXCC entity, and per CQ zone and continent with the
+    /// prefixes of all entities in it. Entities no longer valid are skipped.
+    pub fn add_dxcc_regions(&mut self, regions: &[Dxcc]) {
+        for region in regions {
+            if !region.valid_end.is_empty() {
+                continue;
+            }
+            let prefixes: Vec<String> = region.prefix.split(',').map(ToString::to_string).collect();
+            self.add_region(
+                region.name.to_lowercase().replace(char::is_whitespace, "_"),
+                prefixes.clone(),
+            );
+            let zones = region.cq.iter().map(|cq| format!("CQ_{cq}"));
+            for name in zones.chain(region.continent.iter().cloned()) {
+                let mut new_prefixes = prefixes.clone();
+                if let Some(r) = self.get_region(&name) {
+                    new_prefixes.extend(r.prefixes.iter().cloned());
+                }
+                self.add_region(name, new_prefixes);
+            }
+        }
+    }
+
+    /// The CQ zone comes from the country file if one is loaded, else from
+    /// the regions like entity and continent.
+    pub fn locate(&self, callsign: &str) -> StationLocation {
+        locate(
+            self.regions.values().map(Arc::as_ref),
+            &self.locations,
+            callsign,
+        )
+    }
+
+    /// The regions and country file, to locate stations without the
+    /// database lock.
+    pub fn locator(&self) -> Locator {
+        Locator {
+            regions: self.regions.values().cloned().collect(),
+            locations: self.locations.clone(),
+        }
+    }
+}
+
+/// Locates stations like `SpotDB::locate`, from a copy of its regions.
+#[derive(Clone)]
+pub struct Locator {
+    regions: Vec<Arc<Region>>,
+    locations: Arc<Locations>,
+}
+
+impl Locator {
+    pub fn locate(&self, callsign: &str) -> StationLocation {
+        locate(
+            self.regions.iter().map(Arc::as_ref),
+            &self.locations,
+            callsign,
+        )
+    }
+}
+
+fn locate<'a>(
+    regions: impl Iterator<Item = &'a Region>,
+    locations: &Locations,
+    callsign: &str,
+) -> StationLocation {
+    // kind -> (prefix length, region, tied with another region)
+    let mut best: [Option<(usize, &str, bool)>; 3] = [None; 3];
+    for region in regions {
+        let Some(len) = region.match_len(callsign) else {
+            continue;
+        };
+        let slot = &mut best[region.kind() as usize];
+        match slot {
+            Some((best_len, _, tied)) if len == *best_len => *tied = true,
+            Some((best_len, _, _)) if len < *best_len => {}
+            _ => *slot = Some((len, &region.name, false)),
+        }
+    }
+    let [entity, cq_zone, continent] = best.map(|b| {
+        b.filter(|(_, _, tied)| !tied)
+            .map(|(_, name, _)| name.to_string())
+    });
+    StationLocation {
+        entity,
+        continent,
+        cq_zone: locations
+            .cq_zone(callsign)
+            .map(|z| format!("CQ_{z}"))
+            .or(cq_zone),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn locate_by_longest_prefix() {
+        let mut db = SpotDB::default();
+        let prefixes = |p: &[&str]| p.iter().map(ToString::to_string).collect();
+        db.add_region("united_states".to_string(), prefixes(&["K", "W"]));
+        db.add_region("hawaii".to_string(), prefixes(&["KH6"]));
+        db.add_region("NA".to_string(), prefixes(&["K", "W"]));
+        db.add_region("OC".to_string(), prefixes(&["KH6"]));
+        db.add_region("CQ_5".to_string(), prefixes(&["K", "W"]));
+        db.add_region("CQ_31".to_string(), prefixes(&["KH6"]));
+
+        let location = db.locate("KH6XYZ");
+        assert_eq!(location.entity.as_deref(), Some("hawaii"));
+        assert_eq!(location.continent.as_deref(), Some("OC"));
+        assert_eq!(location.cq_zone.as_deref(), Some("CQ_31"));
+        assert_eq!(db.locate("W1AW").entity.as_deref(), Some("united_states"));
+        assert_eq!(db.locate("DL1ABC"), StationLocation::default());
+    }
+
+    #[rstest]
+    fn locate_with_shipped_regions() {
+        let mut db = SpotDB::default();
+        db.add_dxcc_regions(&crate::region_loader::load("data/dxcc.json").unwrap());
+        let location = db.locate("DL1ABC");
+        assert_eq!(location.entity.as_deref(), Some("germany"));
+        assert_eq!(location.cq_zone.as_deref(), Some("CQ_14"));
+        assert_eq!(location.continent.as_deref(), Some("EU"));
+        // entities spanning several zones don't tell the zone
+        for call in ["K1ABC", "W6XYZ", "VE3ABC", "VK2ABC", "UA9ABC", "BY1ABC"] {
+            let location = db.locate(call);
+            assert!(location.entity.is_some(), "{call}");
+            assert_eq!(location.cq_zone, None, "{call}");
+        }
+
+        let mut locations = crate::geo::Locations::default();
+        locations
+            .add_country_file(
+                "United States: 05: 08: NA: 37.53: 91.67: 5.0: K:\n    K,W,K6(3),W6(3);",
+            )
+            .unwrap();
+        db.set_locations(locations);
+        assert_eq!(db.locate("K1ABC").cq_zone.as_deref(), Some("CQ_5"));
+        assert_eq!(db.locate("W6XYZ").cq_zone.as_deref(), Some("CQ_3"));
+    }
+}
@@ -0,0 +1,69 @@
+use super::{Spot, SpotDB, SpotId};
+use crate::activations::ProgramReference;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Estimated memory usage per component in bytes. Counts the payload of
+/// strings and collection entries, not allocator overhead.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MemoryUsage {
+    pub spots: usize,
+    pub indexes: usize,
+    pub signals: usize,
+    pub regions: usize,
+    pub out_of_band: usize,
+    pub beacons: usize,
+    pub total: usize,
+}
+
+/// Heap usage of a program reference, including its own size.
+pub(super) fn reference_bytes(reference: &ProgramReference) -> usize {
+    size_of::<ProgramReference>()
+        + reference.reference.capacity()
+        + reference.name.as_ref().map_or(0, String::capacity)
+}
+
+impl Spot {
+    pub(super) fn memory_bytes(&self) -> usize {
+        // the spot lives in an Arc, which adds the two reference counts
+        size_of::<Spot>()
+            + 2 * size_of::<usize>()
+            + self.spotter.capacity()
+            + self.spotted.capacity()
+            + self.mode.capacity()
+            + self.msg.capacity()
+            + self.band.as_ref().map_or(0, String::capacity)
+            + self.references.iter().map(reference_bytes).sum::<usize>()
+    }
+}
+
+impl SpotDB {
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let (spots, indexes) = self.spots.memory_bytes();
+        let indexes = indexes
+            + self
+                .band_calls
+                .read()
+                .expect("RwLock poisoned")
+                .memory_bytes()
+            + self.out_of_segment.len() * size_of::<SpotId>();
+        let signals = self.signals.memory_bytes();
+        let regions = self.regions.values().map(|r| r.memory_bytes()).sum();
+        let kept_apart = |spots: &VecDeque<Arc<Spot>>| {
+            spots.iter().map(|s| s.memory_bytes()).sum::<usize>()
+                + spots.capacity() * size_of::<Arc<Spot>>()
+        };
+        let out_of_band = kept_apart(&self.out_of_band);
+        let beacons = kept_apart(&self.beacons);
+        MemoryUsage {
+            spots,
+            indexes,
+            signals,
+            regions,
+            out_of_band,
+            beacons,
+            total: spots + indexes + signals + regions + out_of_band + beacons,
+        }
+    }
+}
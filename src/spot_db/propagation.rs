@@ -0,0 +1,333 @@
+use super::{Locator, Region, SpotDB, median, window_start};
+use crate::bands::BandPlan;
+use crate::space_weather::SpaceWeather;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A change of the median SNR by at least this much is a trend.
+const TREND_THRESHOLD_DB: i32 = 3;
+
+/// SNR distribution of the spots in one time window.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct SnrSummary {
+    pub num_spots: usize,
+    pub num_calls: usize,
+    pub median_snr_db: Option<i32>,
+    pub p10_snr_db: Option<i32>,
+    pub p90_snr_db: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Improving,
+    Stable,
+    Declining,
+}
+
+/// Propagation to one continent or CQ zone.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AreaPropagation {
+    pub name: String,
+    pub current: SnrSummary,
+    pub previous: SnrSummary,
+    pub trend: Option<Trend>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BandPropagation {
+    pub band: String,
+    pub current: SnrSummary,
+    pub previous: SnrSummary,
+    pub trend: Option<Trend>,
+    pub continents: Vec<AreaPropagation>,
+    pub zones: Vec<AreaPropagation>,
+}
+
+/// What the skimmers of a region hear per band, in the last window compared
+/// to the window before.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Propagation {
+    pub region: String,
+    pub window_mins: u64,
+    /// latest report at the end of each window
+    pub space_weather: Option<SpaceWeather>,
+    pub previous_space_weather: Option<SpaceWeather>,
+    pub bands: Vec<BandPropagation>,
+}
+
+/// Element at `p` percent of the sorted `values`.
+fn percentile(sorted: &[i32], p: usize) -> Option<i32> {
+    if sorted.is_empty() {
+        return None;
+    }
+    Some(sorted[(sorted.len() - 1) * p / 100])
+}
+
+// spotted call, SNR in dB
+type SnrSpot<'a> = (&'a str, i32);
+
+impl SnrSummary {
+    fn from_spots(spots: &[SnrSpot]) -> Self {
+        let mut snr: Vec<i32> = spots.iter().map(|(_, snr_db)| *snr_db).collect();
+        let median_snr_db = median(&mut snr);
+        let calls: HashSet<&str> = spots.iter().map(|(call, _)| *call).collect();
+        Self {
+            num_spots: spots.len(),
+            num_calls: calls.len(),
+            median_snr_db,
+            p10_snr_db: percentile(&snr, 10),
+            p90_snr_db: percentile(&snr, 90),
+        }
+    }
+}
+
+/// `None` if nothing was heard in either window.
+fn trend(current: &SnrSummary, previous: &SnrSummary) -> Option<Trend> {
+    match (current.median_snr_db, previous.median_snr_db) {
+        (Some(_), None) => Some(Trend::Improving),
+        (None, Some(_)) => Some(Trend::Declining),
+        (Some(c), Some(p)) if c - p >= TREND_THRESHOLD_DB => Some(Trend::Improving),
+        (Some(c), Some(p)) if p - c >= TREND_THRESHOLD_DB => Some(Trend::Declining),
+        (Some(_), Some(_)) => Some(Trend::Stable),
+        (None, None) => None,
+    }
+}
+
+// area -> (spots in the current window, spots in the previous window)
+type AreaSpots<'a> = BTreeMap<String, (Vec<SnrSpot<'a>>, Vec<SnrSpot<'a>>)>;
+// current, previous, continents, zones
+type BandSpots<'a> = (
+    Vec<SnrSpot<'a>>,
+    Vec<SnrSpot<'a>>,
+    AreaSpots<'a>,
+    AreaSpots<'a>,
+);
+
+fn areas(spots: AreaSpots) -> Vec<AreaPropagation> {
+    spots
+        .into_iter()
+        .map(|(name, (current, previous))| {
+            let current = SnrSummary::from_spots(&current);
+            let previous = SnrSummary::from_spots(&previous);
+            AreaPropagation {
+                name,
+                trend: trend(&current, &previous),
+                current,
+                previous,
+            }
+        })
+        .collect()
+}
+
+/// What a propagation view is computed from, taken under the database lock
+/// so the view can be computed without holding it.
+pub struct PropagationSnapshot {
+    region: Arc<Region>,
+    window: Duration,
+    now: DateTime<Utc>,
+    locator: Locator,
+    band_plan: Arc<BandPlan>,
+    space_weather: Option<SpaceWeather>,
+    previous_space_weather: Option<SpaceWeather>,
+}
+
+impl SpotDB {
+    /// Propagation as heard by the skimmers of `region`, from normal and
+    /// beacon spots, over the last `window` and the `window` before it.
+    /// `None` if the region does not exist.
+    pub fn get_propagation(
+        &self,
+        region: &str,
+        window: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<Propagation> {
+        Some(
+            self.propagation_snapshot(region, window, now)?
+                .propagation(),
+        )
+    }
+
+    /// What `get_propagation` computes the propagation view from.
+    pub fn propagation_snapshot(
+        &self,
+        region: &str,
+        window: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<PropagationSnapshot> {
+        Some(PropagationSnapshot {
+            region: self.get_region(region)?.clone(),
+            window,
+            now,
+            locator: self.locator(),
+            band_plan: self.band_plan.clone(),
+            space_weather: self.space_weather_at(now).cloned(),
+            previous_space_weather: self.space_weather_at(window_start(now, window)).cloned(),
+        })
+    }
+}
+
+impl PropagationSnapshot {
+    /// See `SpotDB::get_propagation`, from the normal and beacon spots in the
+    /// region's aggregates.
+    pub fn propagation(self) -> Propagation {
+        let Self {
+            region,
+            window,
+            now,
+            locator,
+            band_plan,
+            space_weather,
+            previous_space_weather,
+        } = self;
+        let start = window_start(now, window);
+        let previous_start = window_start(start, window);
+        let mut locations = HashMap::new();
+        let region_spots = region.spots.read().expect("RwLock poisoned");
+        let spots = region_spots
+            .aggregate
+            .snr_since(previous_start)
+            .chain(region_spots.beacons.snr_since(previous_start));
+        let mut by_band: HashMap<&str, BandSpots> = HashMap::new();
+        for (band, call, timestamp, snr_db) in spots {
+            let spot = (call, snr_db);
+            let current = timestamp >= start;
+            let location = locations
+                .entry(call)
+                .or_insert_with(|| locator.locate(call));
+            let (band_current, band_previous, continents, zones) = by_band.entry(band).or_default();
+            if current {
+                band_current.push(spot);
+            } else {
+                band_previous.push(spot);
+            }
+            let areas = [
+                (continents, &location.continent),
+                (zones, &location.cq_zone),
+            ];
+            for (areas, name) in areas {
+                if let Some(name) = name {
+                    let (area_current, area_previous) = areas.entry(name.clone()).or_default();
+                    if current {
+                        area_current.push(spot);
+                    } else {
+                        area_previous.push(spot);
+                    }
+                }
+            }
+        }
+
+        let bands = band_plan
+            .bands()
+            .iter()
+            .filter_map(|band| {
+                let (current, previous, continents, zones) = by_band.remove(band.name.as_str())?;
+                let current = SnrSummary::from_spots(&current);
+                let previous = SnrSummary::from_spots(&previous);
+                Some(BandPropagation {
+                    band: band.name.clone(),
+                    trend: trend(&current, &previous),
+                    current,
+                    previous,
+                    continents: areas(continents),
+                    zones: areas(zones),
+                })
+            })
+            .collect();
+        Propagation {
+            region: region.name.clone(),
+            window_mins: window.as_secs() / 60,
+            space_weather,
+            previous_space_weather,
+            bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    #[case(&[], 50, None)]
+    #[case(&[1], 90, Some(1))]
+    #[case(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], 10, Some(2))]
+    #[case(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], 90, Some(10))]
+    fn percentiles(#[case] sorted: &[i32], #[case] p: usize, #[case] expected: Option<i32>) {
+        assert_eq!(percentile(sorted, p), expected);
+    }
+
+    #[rstest]
+    fn propagation_per_band_and_area() {
+        let mut db = SpotDB::default();
+        let prefixes = |p: &[&str]| p.iter().map(ToString::to_string).collect();
+        db.add_region("europe".to_string(), prefixes(&["DL"]));
+        db.add_region("NA".to_string(), prefixes(&["K", "W"]));
+        db.add_region("CQ_5".to_string(), prefixes(&["K", "W"]));
+        db.add_region("AS".to_string(), prefixes(&["JA"]));
+        let now = Utc::now();
+        let earlier = now - Duration::from_secs(20 * 60);
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 10, 25, "CQ", earlier);
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 20, 25, "CQ", now);
+        db.add_spot("DL2BBB", "W1XYZ", f, "CW", 22, 25, "CQ", now);
+        db.add_spot("DL1AAA", "JA1ABC", f, "CW", 5, 25, "CQ", earlier);
+        // not a skimmer of the region
+        db.add_spot("F1CCC", "K2ABC", f, "CW", 30, 25, "CQ", now);
+
+        let window = Duration::from_secs(15 * 60);
+        let propagation = db.get_propagation("europe", window, now).unwrap();
+        assert_eq!(propagation.bands.len(), 1);
+        let twenty = &propagation.bands[0];
+        assert_eq!(twenty.current.num_spots, 2);
+        assert_eq!(twenty.current.median_snr_db, Some(22));
+        assert_eq!(twenty.previous.num_spots, 2);
+
+        let continents: Vec<(&str, Option<Trend>)> = twenty
+            .continents
+            .iter()
+            .map(|c| (c.name.as_str(), c.trend))
+            .collect();
+        assert_eq!(
+            continents,
+            vec![
+                ("AS", Some(Trend::Declining)),
+                ("NA", Some(Trend::Improving))
+            ]
+        );
+        assert_eq!(twenty.zones[0].name, "CQ_5");
+        assert_eq!(twenty.zones[0].current.num_calls, 2);
+        assert!(db.get_propagation("nowhere", window, now).is_none());
+        // windows beyond all time see every spot as current
+        let forever = Duration::from_secs(u64::MAX);
+        let propagation = db.get_propagation("europe", forever, now).unwrap();
+        assert_eq!(propagation.bands[0].current.num_spots, 4);
+    }
+
+    #[rstest]
+    fn propagation_zones_of_shipped_regions() {
+        let mut db = SpotDB::default();
+        db.add_dxcc_regions(&crate::region_loader::load("data/dxcc.json").unwrap());
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        db.add_spot("DL1AAA", "K1ABC", f, "CW", 20, 25, "CQ", now);
+        db.add_spot("DL1AAA", "OH1ABC", f, "CW", 20, 25, "CQ", now);
+
+        let window = Duration::from_secs(15 * 60);
+        let propagation = db.get_propagation("EU", window, now).unwrap();
+        // the zone of K1ABC depends on the call area, which the regions
+        // don't know
+        let zones: Vec<&str> = propagation.bands[0]
+            .zones
+            .iter()
+            .map(|z| z.name.as_str())
+            .collect();
+        assert_eq!(zones, vec!["CQ_15"]);
+    }
+}
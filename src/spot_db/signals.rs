@@ -0,0 +1,303 @@
+use super::{Spot, SpotDB, SpotId};
+use crate::activations::Program;
+use crate::solar::SolarState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+use uom::si::frequency::{hertz, kilohertz};
+
+/// Spots of the same call this close to the first spot of a signal belong to it.
+const SIGNAL_TOLERANCE_HZ: i64 = 300;
+/// Spots of the same call within this time after the first spot of a signal
+/// belong to it, skimmers report a CQ within a minute or so.
+const SIGNAL_WINDOW: Duration = Duration::from_secs(2 * 60);
+
+/// Identifies a signal for its whole life in the index.
+pub type SignalId = u64;
+
+#[derive(Debug)]
+struct SignalEntry {
+    callsign: String,
+    band: String,
+    freq_hz: i64, // of the first spot
+    first_seen: DateTime<Utc>,
+    spots: Vec<SpotId>,
+}
+
+/// Groups the spots of one transmission, reported by many skimmers, into a
+/// signal. Updated whenever a spot is added or removed.
+#[derive(Debug, Default)]
+pub(super) struct SignalIndex {
+    next_id: SignalId,
+    signals: HashMap<SignalId, SignalEntry>,
+    by_call: HashMap<String, Vec<SignalId>>,
+    of_spot: HashMap<SpotId, SignalId>,
+}
+
+impl SignalIndex {
+    /// Adds the spot to a matching signal or starts a new one.
+    pub(super) fn add(&mut self, id: SpotId, spot: &Spot) -> SignalId {
+        let band = spot.band.clone().unwrap_or_default();
+        let freq_hz = spot.freq.get::<hertz>().round() as i64;
+        if !self.by_call.contains_key(&spot.spotted) {
+            self.by_call.insert(spot.spotted.clone(), Vec::new());
+        }
+        let candidates = self.by_call.get_mut(&spot.spotted).expect("inserted above");
+        let signal_id = candidates
+            .iter()
+            .copied()
+            .find(|s| {
+                let entry = &self.signals[s];
+                entry.band == band
+                    && (entry.freq_hz - freq_hz).abs() <= SIGNAL_TOLERANCE_HZ
+                    && (spot.timestamp - entry.first_seen)
+                        .abs()
+                        .to_std()
+                        .is_ok_and(|d| d <= SIGNAL_WINDOW)
+            })
+            .unwrap_or_else(|| {
+                let signal_id = self.next_id;
+                self.next_id += 1;
+                candidates.push(signal_id);
+                self.signals.insert(
+                    signal_id,
+                    SignalEntry {
+                        callsign: spot.spotted.clone(),
+                        band,
+                        freq_hz,
+                        first_seen: spot.timestamp,
+                        spots: Vec::new(),
+                    },
+                );
+                signal_id
+            });
+        if let Some(entry) = self.signals.get_mut(&signal_id) {
+            entry.first_seen = entry.first_seen.min(spot.timestamp);
+            entry.spots.push(id);
+        }
+        self.of_spot.insert(id, signal_id);
+        signal_id
+    }
+
+    /// Removes the spot, and its signal once it has no spots left.
+    pub(super) fn remove(&mut self, id: SpotId) {
+        let Some(signal_id) = self.of_spot.remove(&id) else {
+            return;
+        };
+        let Some(entry) = self.signals.get_mut(&signal_id) else {
+            return;
+        };
+        entry.spots.retain(|s| *s != id);
+        if !entry.spots.is_empty() {
+            return;
+        }
+        let callsign = entry.callsign.clone();
+        self.signals.remove(&signal_id);
+        if let Some(ids) = self.by_call.get_mut(&callsign) {
+            ids.retain(|s| *s != signal_id);
+            if ids.is_empty() {
+                self.by_call.remove(&callsign);
+            }
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub(super) fn memory_bytes(&self) -> usize {
+        let signals: usize = self
+            .signals
+            .values()
+            .map(|s| {
+                size_of::<(SignalId, SignalEntry)>()
+                    + s.callsign.capacity()
+                    + s.band.capacity()
+                    + s.spots.capacity() * size_of::<SpotId>()
+            })
+            .sum();
+        let by_call: usize = self
+            .by_call
+            .iter()
+            .map(|(call, ids)| {
+                size_of::<(String, Vec<SignalId>)>()
+                    + call.capacity()
+                    + ids.capacity() * size_of::<SignalId>()
+            })
+            .sum();
+        signals + by_call + self.of_spot.len() * size_of::<(SpotId, SignalId)>()
+    }
+}
+
+/// Restricts which signals are returned.
+#[derive(Debug, Clone, Default)]
+pub struct SignalFilter {
+    pub callsign: Option<String>,
+    pub band: Option<String>,
+    /// only skimmers of this region are listed, signals not heard there are dropped
+    pub region: Option<String>,
+    /// only spots activating a reference of these programs, all if empty
+    pub programs: Vec<Program>,
+}
+
+/// How one skimmer heard a signal.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SignalSkimmer {
+    pub spotter: String,
+    pub frequency: f64, // kHz
+    pub snr_db: i32,
+    pub distance_km: Option<u32>,
+    pub bearing_deg: Option<u16>,
+    pub spotter_solar: Option<SolarState>,
+    pub spotted_solar: Option<SolarState>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// One transmission of a station, with all skimmers that reported it.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Signal {
+    pub callsign: String,
+    pub band: String,
+    pub frequency: f64, // kHz, median of the skimmer reports
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub num_spots: usize,
+    pub skimmers: Vec<SignalSkimmer>, // best SNR first
+}
+
+impl SpotDB {
+    pub fn signals_in_db(&self) -> usize {
+        self.signals.len()
+    }
+
+    /// All current signals matching `filter`, newest first.
+    pub fn get_signals(&self, filter: &SignalFilter) -> Vec<Signal> {
+        let region = match &filter.region {
+            Some(name) => match self.get_region(name) {
+                Some(region) => Some(region),
+                None => return Vec::new(),
+            },
+            None => None,
+        };
+        let ids: Box<dyn Iterator<Item = &SignalId>> = match &filter.callsign {
+            Some(call) => Box::new(self.signals.by_call.get(call).into_iter().flatten()),
+            None => Box::new(self.signals.signals.keys()),
+        };
+        let mut signals: Vec<Signal> = ids
+            .filter_map(|id| self.signals.signals.get(id))
+            .filter(|entry| filter.band.as_ref().is_none_or(|b| b == &entry.band))
+            .filter_map(|entry| {
+                let spots: Vec<&Spot> = entry
+                    .spots
+                    .iter()
+                    .filter_map(|id| self.spots.get(*id))
+                    .map(AsRef::as_ref)
+                    .filter(|s| region.is_none_or(|r| r.match_callsign(&s.spotter)))
+                    .filter(|s| s.activates(&filter.programs))
+                    .collect();
+                self.summarize_signal(entry, &spots)
+            })
+            .collect();
+        signals.sort_by(|a, b| {
+            b.last_seen
+                .cmp(&a.last_seen)
+                .then_with(|| a.callsign.cmp(&b.callsign))
+        });
+        signals
+    }
+
+    fn summarize_signal(&self, entry: &SignalEntry, spots: &[&Spot]) -> Option<Signal> {
+        let last_seen = spots.iter().map(|s| s.timestamp).max()?;
+        let first_seen = spots.iter().map(|s| s.timestamp).min()?;
+        // a skimmer may report the same signal twice, keep its newest report
+        let mut by_spotter: HashMap<&str, &Spot> = HashMap::new();
+        for spot in spots {
+            by_spotter
+                .entry(&spot.spotter)
+                .and_modify(|s| {
+                    if spot.timestamp > s.timestamp {
+                        *s = spot;
+                    }
+                })
+                .or_insert(spot);
+        }
+        let mut frequencies: Vec<f64> = spots.iter().map(|s| s.freq.get::<kilohertz>()).collect();
+        frequencies.sort_by(f64::total_cmp);
+        let mut skimmers: Vec<SignalSkimmer> = by_spotter
+            .into_values()
+            .map(|s| SignalSkimmer {
+                spotter: s.spotter.clone(),
+                frequency: s.freq.get::<kilohertz>(),
+                snr_db: s.snr_db,
+                distance_km: s.path.map(|p| p.distance_km),
+                bearing_deg: s.path.map(|p| p.bearing_deg),
+                spotter_solar: s.spotter_solar,
+                spotted_solar: s.spotted_solar,
+                timestamp: s.timestamp,
+            })
+            .collect();
+        skimmers.sort_by_key(|s| (Reverse(s.snr_db), s.spotter.clone()));
+        Some(Signal {
+            callsign: entry.callsign.clone(),
+            band: entry.band.clone(),
+            frequency: frequencies[frequencies.len() / 2],
+            first_seen,
+            last_seen,
+            num_spots: spots.len(),
+            skimmers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+
+    #[rstest]
+    fn group_spots_into_signals() {
+        let mut db = SpotDB::default();
+        db.add_region("europe".to_string(), vec!["DL".to_string()]);
+        let now = Utc::now();
+        let khz = Frequency::new::<kilohertz>;
+        db.add_spot("DL1AAA", "K1ABC", khz(14025.0), "CW", 12, 25, "CQ", now);
+        db.add_spot("F1BBB", "K1ABC", khz(14025.1), "CW", 20, 25, "CQ", now);
+        db.add_spot("W1CCC", "K1ABC", khz(14024.9), "CW", 30, 25, "CQ", now);
+        // same call on another frequency and, later, on the same one again
+        db.add_spot("DL1AAA", "K1ABC", khz(14040.0), "CW", 10, 25, "CQ", now);
+        let later = now + Duration::from_secs(10 * 60);
+        db.add_spot("DL1AAA", "K1ABC", khz(14025.0), "CW", 15, 25, "CQ", later);
+
+        assert_eq!(db.spots_in_db(), 5);
+        assert_eq!(db.signals_in_db(), 3);
+
+        let filter = SignalFilter {
+            callsign: Some("K1ABC".to_string()),
+            ..Default::default()
+        };
+        let signals = db.get_signals(&filter);
+        let grouped = signals.iter().find(|s| s.num_spots == 3).unwrap();
+        assert_eq!(grouped.frequency, 14025.0);
+        let spotters: Vec<&str> = grouped
+            .skimmers
+            .iter()
+            .map(|s| s.spotter.as_str())
+            .collect();
+        assert_eq!(spotters, vec!["W1CCC", "F1BBB", "DL1AAA"]);
+
+        let filter = SignalFilter {
+            region: Some("europe".to_string()),
+            band: Some("20m".to_string()),
+            ..Default::default()
+        };
+        let signals = db.get_signals(&filter);
+        assert_eq!(signals.len(), 3);
+        assert!(signals.iter().all(|s| s.skimmers.len() == 1));
+
+        db.remove_spots_before(later);
+        assert_eq!(db.signals_in_db(), 1);
+    }
+}
@@ -0,0 +1,79 @@
+use super::SpotDB;
+use crate::space_weather::SpaceWeather;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Space weather reports are kept this long.
+const SPACE_WEATHER_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+impl SpotDB {
+    /// Adds a report to the time series, unless one with the same issue time
+    /// is already there, and drops reports older than a week.
+    pub fn add_space_weather(&mut self, weather: SpaceWeather) {
+        let position = self
+            .space_weather
+            .partition_point(|w| w.issued < weather.issued);
+        if self
+            .space_weather
+            .get(position)
+            .is_some_and(|w| w.issued == weather.issued)
+        {
+            return;
+        }
+        let cutoff = weather.issued - SPACE_WEATHER_RETENTION;
+        self.space_weather.insert(position, weather);
+        self.space_weather.retain(|w| w.issued >= cutoff);
+    }
+
+    /// All reports, oldest first.
+    pub fn space_weather(&self) -> &[SpaceWeather] {
+        &self.space_weather
+    }
+
+    /// The newest report issued at or before `time`.
+    pub fn space_weather_at(&self, time: DateTime<Utc>) -> Option<&SpaceWeather> {
+        let position = self.space_weather.partition_point(|w| w.issued <= time);
+        position
+            .checked_sub(1)
+            .and_then(|i| self.space_weather.get(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn report(issued: DateTime<Utc>, solar_flux: u32) -> SpaceWeather {
+        SpaceWeather {
+            issued,
+            solar_flux: Some(solar_flux),
+            a_index: None,
+            k_index: None,
+        }
+    }
+
+    #[rstest]
+    fn space_weather_series() {
+        let mut db = SpotDB::default();
+        let now = Utc::now();
+        let hours = |h: u64| Duration::from_secs(h * 3600);
+        db.add_space_weather(report(now - hours(3), 150));
+        db.add_space_weather(report(now, 160));
+        // polled again before a new report is out
+        db.add_space_weather(report(now, 160));
+        db.add_space_weather(report(now - hours(6), 140));
+        let series: Vec<Option<u32>> = db.space_weather().iter().map(|w| w.solar_flux).collect();
+        assert_eq!(series, vec![Some(140), Some(150), Some(160)]);
+
+        assert_eq!(
+            db.space_weather_at(now - hours(1)).unwrap().solar_flux,
+            Some(150)
+        );
+        assert_eq!(db.space_weather_at(now).unwrap().solar_flux, Some(160));
+        assert!(db.space_weather_at(now - hours(7)).is_none());
+
+        db.add_space_weather(report(now + hours(7 * 24 - 2), 170));
+        assert_eq!(db.space_weather().len(), 2);
+    }
+}
@@ -0,0 +1,174 @@
+use super::{Calibration, Spot, SpotDB, median};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A skimmer without any spot for this long is reported as quiet.
+const QUIET_AFTER: Duration = Duration::from_secs(5 * 60);
+/// A skimmer without any spot for this long is reported as offline.
+const OFFLINE_AFTER: Duration = Duration::from_secs(15 * 60);
+/// A skimmer is forgotten this long after its last spot expired.
+pub(super) const FORGET_AFTER: Duration = Duration::from_secs(24 * 3600);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpotterStatus {
+    Active,
+    Quiet,
+    Offline,
+}
+
+impl SpotterStatus {
+    fn from_last_seen(last_seen: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let silent = (now - last_seen).to_std().unwrap_or_default();
+        if silent >= OFFLINE_AFTER {
+            Self::Offline
+        } else if silent >= QUIET_AFTER {
+            Self::Quiet
+        } else {
+            Self::Active
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SpotterBandStats {
+    pub band: String,
+    pub num_spots: usize,
+    pub num_calls: usize,
+    pub median_snr_db: Option<i32>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SpotterSummary {
+    pub spotter: String,
+    pub regions: Vec<String>,
+    pub status: SpotterStatus,
+    pub last_seen: DateTime<Utc>,
+    pub num_spots: usize,
+    pub median_snr_db: Option<i32>,
+    pub calibration: Option<Calibration>,
+    pub modes: Vec<String>,
+    pub bands: Vec<SpotterBandStats>,
+}
+
+impl SpotDB {
+    /// Summary of a single skimmer, `None` if it was never seen.
+    pub fn get_spotter(&self, spotter: &str) -> Option<SpotterSummary> {
+        let last_seen = *self.spotter_last_seen.get(spotter)?;
+        let spots: Vec<&Spot> = self.spots.by_spotter(spotter).map(AsRef::as_ref).collect();
+        Some(self.summarize_spotter(spotter, last_seen, &spots, Utc::now()))
+    }
+
+    /// Summaries of all skimmers ever seen, sorted by callsign.
+    /// Skimmers whose spots have all expired are kept and reported offline.
+    pub fn get_spotters(&self) -> Vec<SpotterSummary> {
+        let mut by_spotter: HashMap<&str, Vec<&Spot>> = HashMap::new();
+        for spot in self.spots.iter() {
+            by_spotter.entry(&spot.spotter).or_default().push(spot);
+        }
+        let now = Utc::now();
+        let mut summaries: Vec<SpotterSummary> = self
+            .spotter_last_seen
+            .iter()
+            .map(|(spotter, last_seen)| {
+                let spots = by_spotter.get(spotter.as_str()).map_or(&[][..], |s| s);
+                self.summarize_spotter(spotter, *last_seen, spots, now)
+            })
+            .collect();
+        summaries.sort_unstable_by(|a, b| a.spotter.cmp(&b.spotter));
+        summaries
+    }
+
+    fn summarize_spotter(
+        &self,
+        spotter: &str,
+        last_seen: DateTime<Utc>,
+        spots: &[&Spot],
+        now: DateTime<Utc>,
+    ) -> SpotterSummary {
+        let mut by_band: HashMap<&str, Vec<&Spot>> = HashMap::new();
+        for spot in spots {
+            if let Some(band) = &spot.band {
+                by_band.entry(band).or_default().push(spot);
+            }
+        }
+        let bands = self
+            .band_plan
+            .bands()
+            .iter()
+            .filter_map(|b| by_band.get(b.name.as_str()).map(|spots| (&b.name, spots)))
+            .map(|(band, spots)| {
+                let mut calls: Vec<&str> = spots.iter().map(|s| s.spotted.as_str()).collect();
+                calls.sort_unstable();
+                calls.dedup();
+                let mut snr: Vec<i32> = spots.iter().map(|s| s.snr_db).collect();
+                SpotterBandStats {
+                    band: band.clone(),
+                    num_spots: spots.len(),
+                    num_calls: calls.len(),
+                    median_snr_db: median(&mut snr),
+                    last_seen: spots.iter().map(|s| s.timestamp).max().unwrap_or_default(),
+                }
+            })
+            .collect();
+        let mut modes: Vec<String> = spots.iter().map(|s| s.mode.clone()).collect();
+        modes.sort_unstable();
+        modes.dedup();
+        let mut snr: Vec<i32> = spots.iter().map(|s| s.snr_db).collect();
+        SpotterSummary {
+            spotter: spotter.to_string(),
+            regions: self.regions_of(spotter),
+            status: SpotterStatus::from_last_seen(last_seen, now),
+            last_seen,
+            num_spots: spots.len(),
+            median_snr_db: median(&mut snr),
+            calibration: self.get_calibration(spotter),
+            modes,
+            bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    #[case(60, SpotterStatus::Active)]
+    #[case(6 * 60, SpotterStatus::Quiet)]
+    #[case(20 * 60, SpotterStatus::Offline)]
+    fn spotter_status(#[case] silent_secs: u64, #[case] expected: SpotterStatus) {
+        let now = Utc::now();
+        let last_seen = now - Duration::from_secs(silent_secs);
+        assert_eq!(SpotterStatus::from_last_seen(last_seen, now), expected);
+    }
+
+    #[rstest]
+    fn spotter_survives_cleanup() {
+        let mut db = SpotDB::default();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        let earlier = Utc::now() - Duration::from_secs(3600);
+        db.add_spot("HB9HUS", "K1ABC", f, "CW", 10, 25, "CQ", earlier);
+        db.add_spot("HB9HUS", "K1XYZ", f, "RTTY", 20, 45, "CQ", earlier);
+        let summary = db.get_spotter("HB9HUS").expect("spotter missing");
+        assert_eq!(summary.num_spots, 2);
+        assert_eq!(summary.modes, vec!["CW", "RTTY"]);
+        assert_eq!(summary.bands[0].num_calls, 2);
+
+        db.cleanup_old_spots(Duration::from_secs(1000));
+        let spotters = db.get_spotters();
+        assert_eq!(spotters.len(), 1);
+        assert_eq!(spotters[0].num_spots, 0);
+        assert_eq!(spotters[0].status, SpotterStatus::Offline);
+
+        db.remove_spots_before(earlier + FORGET_AFTER + Duration::from_secs(1));
+        assert!(db.get_spotters().is_empty());
+        assert!(db.get_spotter("HB9HUS").is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use super::Spot;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use uom::si::f64::Frequency;
+use uom::si::frequency::hertz;
+
+/// Identifies a spot for its whole life in the store.
+pub type SpotId = u64;
+
+/// All spots, indexed by time for expiry, by spotted callsign, by spotter and
+/// by frequency.
+#[derive(Debug, Default)]
+pub(super) struct SpotStore {
+    next_id: SpotId,
+    spots: HashMap<SpotId, Arc<Spot>>,
+    by_time: BTreeSet<(DateTime<Utc>, SpotId)>,
+    by_call: HashMap<String, BTreeSet<SpotId>>,
+    by_spotter: HashMap<String, BTreeSet<SpotId>>,
+    by_freq: BTreeSet<(i64, SpotId)>,
+}
+
+fn freq_key(freq: Frequency) -> i64 {
+    freq.get::<hertz>().round() as i64
+}
+
+impl SpotStore {
+    pub(super) fn insert(&mut self, spot: Arc<Spot>) -> SpotId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.by_time.insert((spot.timestamp, id));
+        self.by_call
+            .entry(spot.spotted.clone())
+            .or_default()
+            .insert(id);
+        self.by_spotter
+            .entry(spot.spotter.clone())
+            .or_default()
+            .insert(id);
+        self.by_freq.insert((freq_key(spot.freq), id));
+        self.spots.insert(id, spot);
+        id
+    }
+
+    /// Removes and returns all spots older than `cutoff`.
+    pub(super) fn remove_before(&mut self, cutoff: DateTime<Utc>) -> Vec<(SpotId, Arc<Spot>)> {
+        let active = self.by_time.split_off(&(cutoff, 0));
+        let expired = std::mem::replace(&mut self.by_time, active);
+        expired
+            .into_iter()
+            .filter_map(|(_, id)| self.remove(id).map(|spot| (id, spot)))
+            .collect()
+    }
+
+    /// Removes and returns the `n` oldest spots.
+    pub(super) fn remove_oldest(&mut self, n: usize) -> Vec<(SpotId, Arc<Spot>)> {
+        let oldest: Vec<SpotId> = (0..n)
+            .map_while(|_| self.by_time.pop_first())
+            .map(|(_, id)| id)
+            .collect();
+        oldest
+            .into_iter()
+            .filter_map(|id| self.remove(id).map(|spot| (id, spot)))
+            .collect()
+    }
+
+    /// Time of the oldest spot, `None` if empty.
+    pub(super) fn oldest_time(&self) -> Option<DateTime<Utc>> {
+        self.by_time.first().map(|(time, _)| *time)
+    }
+
+    fn remove(&mut self, id: SpotId) -> Option<Arc<Spot>> {
+        let spot = self.spots.remove(&id)?;
+        remove_from_index(&mut self.by_call, &spot.spotted, id);
+        remove_from_index(&mut self.by_spotter, &spot.spotter, id);
+        self.by_freq.remove(&(freq_key(spot.freq), id));
+        Some(spot)
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.spots.len()
+    }
+
+    pub(super) fn get(&self, id: SpotId) -> Option<&Arc<Spot>> {
+        self.spots.get(&id)
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = &Arc<Spot>> {
+        self.spots.values()
+    }
+
+    pub(super) fn by_call(&self, call: &str) -> impl Iterator<Item = &Arc<Spot>> {
+        self.lookup(self.by_call.get(call))
+    }
+
+    pub(super) fn by_spotter(&self, spotter: &str) -> impl Iterator<Item = &Arc<Spot>> {
+        self.lookup(self.by_spotter.get(spotter))
+    }
+
+    /// All spots reported between `lower` and `upper`.
+    pub(super) fn in_range(
+        &self,
+        lower: Frequency,
+        upper: Frequency,
+    ) -> impl Iterator<Item = &Arc<Spot>> {
+        self.by_freq
+            .range((freq_key(lower), SpotId::MIN)..=(freq_key(upper), SpotId::MAX))
+            .filter_map(|(_, id)| self.spots.get(id))
+    }
+
+    /// Estimated heap usage in bytes of the spots and of the indexes.
+    pub(super) fn memory_bytes(&self) -> (usize, usize) {
+        let spots = self.spots.values().map(|s| s.memory_bytes()).sum::<usize>()
+            + self.spots.capacity() * size_of::<(SpotId, Arc<Spot>)>();
+        let by_name = |index: &HashMap<String, BTreeSet<SpotId>>| {
+            index
+                .iter()
+                .map(|(name, ids)| {
+                    size_of::<(String, BTreeSet<SpotId>)>()
+                        + name.capacity()
+                        + ids.len() * size_of::<SpotId>()
+                })
+                .sum::<usize>()
+        };
+        let indexes = self.by_time.len() * size_of::<(DateTime<Utc>, SpotId)>()
+            + by_name(&self.by_call)
+            + by_name(&self.by_spotter)
+            + self.by_freq.len() * size_of::<(i64, SpotId)>();
+        (spots, indexes)
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        ids: Option<&'a BTreeSet<SpotId>>,
+    ) -> impl Iterator<Item = &'a Arc<Spot>> {
+        ids.into_iter()
+            .flatten()
+            .filter_map(|id| self.spots.get(id))
+    }
+}
+
+fn remove_from_index(index: &mut HashMap<String, BTreeSet<SpotId>>, key: &str, id: SpotId) {
+    if let Some(ids) = index.get_mut(key) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spot_db::SpotDB;
+    use rstest::rstest;
+    use std::time::Duration;
+    use uom::si::frequency::kilohertz;
+
+    #[rstest]
+    fn indexes_follow_expiry() {
+        let mut db = SpotDB::default();
+        let now = Utc::now();
+        let old = now - Duration::from_secs(3600);
+        let khz = Frequency::new::<kilohertz>;
+        db.add_spot("DL1AAA", "K1ABC", khz(14025.0), "CW", 10, 25, "CQ", old);
+        db.add_spot("DL1AAA", "K1ABC", khz(14026.0), "CW", 10, 25, "CQ", now);
+        db.add_spot("F1BBB", "K1XYZ", khz(7010.0), "CW", 10, 25, "CQ", now);
+
+        let store = &db.spots;
+        assert_eq!(store.by_call("K1ABC").count(), 2);
+        assert_eq!(store.in_range(khz(14000.0), khz(14350.0)).count(), 2);
+
+        db.remove_spots_before(now - Duration::from_secs(60));
+        let store = &db.spots;
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.by_call("K1ABC").count(), 1);
+        assert_eq!(store.by_spotter("DL1AAA").count(), 1);
+        let in_range: Vec<f64> = store
+            .in_range(khz(14000.0), khz(14350.0))
+            .map(|s| s.freq.get::<kilohertz>())
+            .collect();
+        assert_eq!(in_range, vec![14026.0]);
+    }
+}
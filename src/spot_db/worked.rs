@@ -0,0 +1,244 @@
+use super::{Locator, RegionActivity, SpotDB};
+use crate::adif::Qso;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// What a spotted call on a band and in a mode would be for a user, most
+/// needed first.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkedStatus {
+    /// entity never worked
+    NewOne,
+    /// entity not worked on this band
+    NewBand,
+    /// entity not worked on this band in this mode
+    NewMode,
+    Worked,
+}
+
+/// Entities a user has worked, per band and mode.
+#[derive(Debug, Default)]
+pub struct WorkedLog {
+    // entity -> band -> mode -> confirmed
+    entities: HashMap<String, HashMap<String, HashMap<String, bool>>>,
+    num_qsos: usize,
+}
+
+impl WorkedLog {
+    /// Status of a slot and whether it is confirmed.
+    pub fn status(&self, entity: &str, band: &str, mode: &str) -> (WorkedStatus, bool) {
+        let Some(bands) = self.entities.get(entity) else {
+            return (WorkedStatus::NewOne, false);
+        };
+        let Some(modes) = bands.get(band) else {
+            return (WorkedStatus::NewBand, false);
+        };
+        match modes.get(mode) {
+            Some(confirmed) => (WorkedStatus::Worked, *confirmed),
+            None => (WorkedStatus::NewMode, false),
+        }
+    }
+
+    pub fn num_qsos(&self) -> usize {
+        self.num_qsos
+    }
+
+    /// See `SpotDB::get_user_status`, with the entities of the calls found
+    /// by `locator`, so it can be computed without the database lock.
+    pub fn user_status(
+        &self,
+        user: &str,
+        activity: &RegionActivity,
+        locator: &Locator,
+    ) -> UserStatus {
+        let mut entities: HashMap<&str, Option<String>> = HashMap::new();
+        let bands = activity
+            .band_activities
+            .iter()
+            .map(|band| {
+                let mut calls: BTreeMap<String, CallStatus> = BTreeMap::new();
+                let heard = band.modes.iter().flat_map(|m| {
+                    m.buckets
+                        .iter()
+                        .flat_map(move |b| &b.calls)
+                        .map(move |c| (c, m))
+                });
+                for (call, mode) in heard {
+                    let entity = entities
+                        .entry(call)
+                        .or_insert_with(|| locator.locate(call).entity);
+                    let Some(entity) = entity else {
+                        continue;
+                    };
+                    let (status, confirmed) = self.status(entity, &band.band, &mode.mode);
+                    if calls.get(call).is_some_and(|c| c.status <= status) {
+                        continue;
+                    }
+                    calls.insert(
+                        call.clone(),
+                        CallStatus {
+                            entity: entity.clone(),
+                            mode: mode.mode.clone(),
+                            status,
+                            confirmed,
+                        },
+                    );
+                }
+                BandStatus {
+                    band: band.band.clone(),
+                    calls,
+                }
+            })
+            .collect();
+        UserStatus {
+            user: user.to_string(),
+            bands,
+        }
+    }
+}
+
+/// A call in the region activity, in the mode it is most needed in.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CallStatus {
+    pub entity: String,
+    pub mode: String,
+    pub status: WorkedStatus,
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BandStatus {
+    pub band: String,
+    /// calls of unknown entity are left out
+    pub calls: BTreeMap<String, CallStatus>,
+}
+
+/// Worked status of the calls of a region activity for one user.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UserStatus {
+    pub user: String,
+    pub bands: Vec<BandStatus>,
+}
+
+impl SpotDB {
+    /// Sets the log of `user`, the QSOs are assigned to the DXCC entities
+    /// of the loaded regions.
+    pub fn set_user_log(&mut self, user: String, qsos: &[Qso]) {
+        let mut log = WorkedLog::default();
+        let mut entities: HashMap<&str, Option<String>> = HashMap::new();
+        for qso in qsos {
+            let entity = entities
+                .entry(&qso.call)
+                .or_insert_with(|| self.locate(&qso.call).entity);
+            let Some(entity) = entity else {
+                continue;
+            };
+            let confirmed = log
+                .entities
+                .entry(entity.clone())
+                .or_default()
+                .entry(qso.band.clone())
+                .or_default()
+                .entry(qso.mode.clone())
+                .or_default();
+            *confirmed |= qso.confirmed;
+            log.num_qsos += 1;
+        }
+        self.logs.insert(user, Arc::new(log));
+    }
+
+    pub fn get_user_log(&self, user: &str) -> Option<&Arc<WorkedLog>> {
+        self.logs.get(user)
+    }
+
+    /// Annotates every call of `activity` with its worked status for `user`.
+    /// `None` if the user has no log.
+    pub fn get_user_status(&self, user: &str, activity: &RegionActivity) -> Option<UserStatus> {
+        let log = self.get_user_log(user)?;
+        Some(log.user_status(user, activity, &self.locator()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spot_db::ActivityQuery;
+    use chrono::Utc;
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use std::time::Duration;
+    use uom::si::f64::Frequency;
+    use uom::si::frequency::kilohertz;
+
+    fn qso(call: &str, band: &str, mode: &str, confirmed: bool) -> Qso {
+        Qso {
+            call: call.to_string(),
+            band: band.to_string(),
+            mode: mode.to_string(),
+            confirmed,
+        }
+    }
+
+    #[rstest]
+    fn worked_status_per_slot() {
+        let mut db = SpotDB::default();
+        let prefixes = |p: &[&str]| p.iter().map(ToString::to_string).collect();
+        db.add_region("europe".to_string(), prefixes(&["DL"]));
+        db.add_region("japan".to_string(), prefixes(&["JA"]));
+        db.add_region("united_states".to_string(), prefixes(&["K", "W"]));
+        db.add_region("canada".to_string(), prefixes(&["VE"]));
+        db.add_region("brazil".to_string(), prefixes(&["PY"]));
+        db.set_user_log(
+            "hb9hus".to_string(),
+            &[
+                qso("JA2XYZ", "20m", "CW", true),
+                qso("K5ZZZ", "20m", "FT8", false),
+                qso("VE3ABC", "40m", "CW", false),
+            ],
+        );
+        assert_eq!(db.get_user_log("hb9hus").unwrap().num_qsos(), 3);
+
+        let now = Utc::now();
+        let f = Frequency::new::<kilohertz>(14025.0);
+        for (call, mode) in [
+            ("JA1ABC", "CW"),
+            ("K1ABC", "CW"),
+            ("K1ABC", "FT8"),
+            ("W1XYZ", "CW"),
+            ("VE1ABC", "CW"),
+            ("PY1ABC", "CW"),
+            ("ZZ1ABC", "CW"),
+        ] {
+            db.add_spot("DL1AAA", call, f, mode, 10, 25, "CQ", now);
+        }
+        let query = ActivityQuery {
+            windows: vec![Duration::from_secs(60)],
+            ..Default::default()
+        };
+        let activity = db.get_region("europe").unwrap().compute_band_activities(
+            db.band_plan(),
+            &query,
+            &HashSet::new(),
+        );
+        let status = db.get_user_status("hb9hus", &activity).unwrap();
+        let twenty = status.bands.iter().find(|b| b.band == "20m").unwrap();
+        let calls: Vec<(&str, WorkedStatus, &str, bool)> = twenty
+            .calls
+            .iter()
+            .map(|(call, s)| (call.as_str(), s.status, s.mode.as_str(), s.confirmed))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                ("JA1ABC", WorkedStatus::Worked, "CW", true),
+                ("K1ABC", WorkedStatus::NewMode, "CW", false),
+                ("PY1ABC", WorkedStatus::NewOne, "CW", false),
+                ("VE1ABC", WorkedStatus::NewBand, "CW", false),
+                ("W1XYZ", WorkedStatus::NewMode, "CW", false),
+            ]
+        );
+        assert!(db.get_user_status("nobody", &activity).is_none());
+    }
+}
@@ -71,7 +71,14 @@ function makeList(arr, urlBase = null, callInfoMap = null) {
   // ---- Determine region (fallback to CQ-14) ----
   const region = getQueryParam('region') || 'CQ_14';
   const {protocol, hostname, port} = window.location;
-  const apiUrl = `${protocol}//${hostname}${port ? ':' + port : ''}/region/${encodeURIComponent(region)}`;
+  // ---- Optional activity windows in minutes (e.g. 2,10,30) and modes ----
+  const params = new URLSearchParams();
+  for (const name of ['windows', 'mode']) {
+    const value = getQueryParam(name);
+    if (value) params.set(name, value);
+  }
+  const query = params.toString() ? `?${params}` : '';
+  const apiUrl = `${protocol}//${hostname}${port ? ':' + port : ''}/region/${encodeURIComponent(region)}${query}`;
 
   let data;
   try {
@@ -132,14 +139,22 @@ function makeList(arr, urlBase = null, callInfoMap = null) {
     const showSpinner = () => $spinner.removeClass('d-none');
     const hideSpinner = () => $spinner.addClass('d-none');
 
-    // Helper: render a simple <ul> of callsigns
-    const renderCalls = calls => {
-      if (!calls.length) {
+    // Helper: render a simple <ul> of stations, closest first
+    const renderCalls = stations => {
+      if (!stations.length) {
         $result.html('<p class="text-muted mb-0">No callsigns found.</p>');
         return;
       }
       const $ul = $('<ul>').addClass('pill-list list-group list-group-flush');
-      calls.forEach(cs => $ul.append($('<li>').addClass('list-group-item p-1').text(cs)));
+      stations.forEach(st => {
+        const age = Math.round((Date.now() - Date.parse(st.last_seen)) / 60000);
+        const tooltip = [
+          `Freq: ${st.frequencies.join(', ')} kHz`,
+          `Skimmers:   ${st.num_skimmers}`,
+          `Last seen:  ${age} min ago`
+        ].join('\n');
+        $ul.append($('<li>').addClass('list-group-item p-1').attr('title', tooltip).text(st.callsign));
+      });
       $result.empty().append($ul);
     };
 
@@ -169,9 +184,9 @@ function makeList(arr, urlBase = null, callInfoMap = null) {
         const resp = await fetch(url);
         if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
 
-        const data = await resp.json(); // { callsigns: [...] }
-        const calls = Array.isArray(data.callsigns) ? data.callsigns : [];
-        renderCalls(calls);
+        const data = await resp.json(); // { callsigns: [...], stations: [...] }
+        const stations = Array.isArray(data.stations) ? data.stations : [];
+        renderCalls(stations);
       } catch (e) {
         console.error(e);
         $result.html(`
@@ -237,9 +252,10 @@ function makeList(arr, urlBase = null, callInfoMap = null) {
       tbody.appendChild(tr);
     };
 
-    addRow('1 min',  b => b.active_1min);
-    addRow('5 min',  b => b.active_5min);
-    addRow('15 min', b => b.active_15min);
+    // one row per activity window, all bands share the same windows
+    (data.band_activities[0]?.buckets ?? []).forEach((bucket, i) => {
+      addRow(`${bucket.window_mins} min`, b => b.buckets[i].calls);
+    });
 
     table.appendChild(tbody);
     tableResponsive.appendChild(table);